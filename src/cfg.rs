@@ -15,6 +15,42 @@ pub struct Cfg {
     pub no_client_dll_dir: Option<PathBuf>,
     pub cfgs_dir: Option<PathBuf>,
     pub half_life_dir: PathBuf,
+    /// Links scripts into `tas/<project>/` in the game directories instead of the game directories directly.
+    #[serde(default)]
+    pub namespace_scripts: bool,
+    /// How many snapshots of each script 'sync' and 'link-hltas --keep-alive' keep in the `.history` dir of the project, `0` turns snapshots off.
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+    /// Snapshots older than this many days are removed, the newest snapshot of a script is always kept.
+    #[serde(default = "default_history_days")]
+    pub history_days: u64,
+    /// What linking does with a copy of a script in a game dir that was edited after its hard link broke.
+    ///
+    /// - Copies in both game dirs with different edits always stop linking.
+    #[serde(default)]
+    pub diverged_scripts: DivergedScripts,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergedScripts {
+    /// Copies the edited copy back into the project.
+    #[default]
+    Pull,
+    /// Asks if the copy should be copied back, otherwise the project version replaces it.
+    ///
+    /// - Acts as `Stop` where nobody can answer, such as in git hooks and the keep alive loops.
+    Ask,
+    /// Stops linking until the copy is dealt with by hand.
+    Stop,
+}
+
+fn default_history_limit() -> usize {
+    100
+}
+
+fn default_history_days() -> u64 {
+    30
 }
 
 impl Default for Cfg {
@@ -26,6 +62,10 @@ impl Default for Cfg {
             no_client_dll_dir: Some(PathBuf::from("NO_CLIENT_DLL")),
             cfgs_dir: Some(PathBuf::from("cfgs")),
             half_life_dir: PathBuf::from("Half-Life"),
+            namespace_scripts: false,
+            history_limit: default_history_limit(),
+            history_days: default_history_days(),
+            diverged_scripts: DivergedScripts::default(),
         }
     }
 }
@@ -42,6 +42,8 @@ pub enum Commands {
     /// Create a new project.
     ///
     /// - The project is created in the 'tas' directory, but can be changed through the config file.
+    /// - Unless disabled, a git repository is set up with the hooks, '.gitignore' and '.gitattributes', and the files are committed.
+    /// - The repository uses 'diff-hltas' to show the changes of .hltas files.
     New {
         project_name: String,
         #[clap(long, short)]
@@ -50,6 +52,11 @@ pub enum Commands {
         init_git: bool,
         #[clap(long)]
         no_init_git: bool,
+        /// Sets the git remote 'origin' to a path, such as a bare repository or a shared directory.
+        ///
+        /// - A missing or empty directory is set up as a bare repository.
+        #[clap(long, conflicts_with = "no_init_git")]
+        git_remote: Option<PathBuf>,
     },
     /// Initializes a new project in an existing directory.
     ///
@@ -62,7 +69,22 @@ pub enum Commands {
         init_git: bool,
         #[clap(long)]
         no_init_git: bool,
+        /// Sets the git remote 'origin' to a path, such as a bare repository or a shared directory.
+        ///
+        /// - A missing or empty directory is set up as a bare repository.
+        #[clap(long, conflicts_with = "no_init_git")]
+        git_remote: Option<PathBuf>,
     },
+    /// Manages projects.
+    Project {
+        #[clap(subcommand)]
+        command: ProjectCommands,
+    },
+    /// Lists the projects with their metadata from project.toml.
+    ///
+    /// - Shows the category, maps, target time, authors and route notes when they are set.
+    /// - Warns when a project was verified on a different engine or Bunnymod XT build than installed.
+    Projects,
     /// Lists all available games.
     ///
     /// - Lists all games installed in the 'Half-Life' directory.
@@ -79,6 +101,8 @@ pub enum Commands {
         /// Detects if the game closed and restarts it.
         ///
         /// - If you use `optim-games`, it will maintain the amount of games specified.
+        /// - Each game is tracked by the PID of its hl.exe, other games that are running don't count.
+        /// - A game that keeps closing is restarted with a growing delay, and given up on after closing 5 times in a row.
         #[clap(long, requires = "optim_games")]
         keep_alive: bool,
         /// Runs the simulator client.
@@ -121,6 +145,17 @@ pub enum Commands {
         /// Useful in running the script with the 'seed' property to specify rng.
         #[clap(long)]
         script: Option<String>,
+        /// Runs the script of a segment from project.toml, by name or number starting from 1.
+        ///
+        /// - Links the project scripts first and warns if the start save is missing.
+        #[clap(long, conflicts_with = "script")]
+        segment: Option<String>,
+        /// Runs the script with a different seed, written as '<shared> <non-shared>', '<shared>' or 'random'.
+        ///
+        /// - Loads a copy of the script with the seed in the '.scratch' directory of the project, the script itself is left alone.
+        /// - Only the shared seed keeps the non-shared seed of the script.
+        #[clap(long)]
+        seed: Option<String>,
         /// Parameters to pass to hl.exe on start.
         #[clap(long, short)]
         params: Option<Vec<String>>,
@@ -134,10 +169,23 @@ pub enum Commands {
     /// Links all .hltas files to the game directory.
     ///
     /// - This command works on running from the project dir or the root dir.
+    /// - Scripts are found recursively with the `include` and `exclude` globs under `[scripts]` in project.toml.
+    /// - Scripts in sub directories keep the same structure in the game directory.
+    /// - Links that the manager created for scripts that no longer exist are removed.
+    /// - The links are tracked in 'hltas_links.toml' in the root dir, other files in the game directory are never removed.
+    /// - With 'namespace_scripts' set in the config, scripts are linked to 'tas/<project>/' and loaded with `bxt_tas_loadscript tas/<project>/<script>`.
+    /// - Otherwise scripts from different projects with the same name are reported as an error.
+    /// - A copy in the game directory that was edited after its link broke is copied back into the project first.
+    /// - Set 'diverged_scripts' in the config to "ask" or "stop" to be asked first or to stop linking instead.
     LinkHLTAS {
         /// Will keep running the command to keep hard linking the hltas files.
+        ///
+        /// - Also snapshots every script that changed, like 'sync'.
         #[clap(long)]
         keep_alive: bool,
+        /// Doesn't link scripts that fail to parse, their existing links are left alone.
+        #[clap(long)]
+        lint: bool,
     },
     /// Syncs the SAVE directory with the primary and secondary game directories.
     ///
@@ -150,5 +198,375 @@ pub enum Commands {
         keep_alive: bool,
     },
     /// Keeps running `LinkHLTAS` and `SyncSaves` commands.
+    ///
+    /// - Also snapshots every script that changed into the `.history` dir of its project, see 'hltas history'.
     Sync,
+    /// Updates the git hooks of projects to the current version.
+    ///
+    /// - The post-checkout, post-merge and post-rewrite hooks run 'link-hltas' for the project.
+    /// - Hooks from older versions are replaced in place, other contents of the hooks are kept.
+    /// - Also registers 'diff-hltas' as the git diff driver for scripts and updates the blocks in '.gitattributes' and '.gitignore'.
+    /// - Ignore lists that older versions appended to '.gitignore' are replaced by the block.
+    /// - Updates the current project, or all projects when run from the root dir.
+    UpdateHooks,
+    /// Reads and edits .hltas scripts.
+    Hltas {
+        #[clap(subcommand)]
+        command: HltasCommands,
+    },
+    /// Shows the changes between 2 versions of a script as frame bulk and property changes.
+    ///
+    /// - Frame bulks are shown with their index and the frame they start on.
+    /// - Changed frame bulks only show the fields that changed.
+    /// - Projects set up by the manager use this for `git diff` and `git log -p` on .hltas files.
+    /// - Takes either `<old> <new>` or the arguments git passes to a diff driver.
+    DiffHltas {
+        #[clap(required = true, num_args = 1..=9)]
+        args: Vec<String>,
+        /// Prints a single script with the index and starting frame of each frame bulk.
+        ///
+        /// - Git uses this for `git log -p`, which doesn't run diff drivers.
+        #[clap(long)]
+        textconv: bool,
+    },
+    /// Manages the block the manager adds to userconfig.cfg.
+    ///
+    /// - The block runs 'hltas.cfg' and is applied to both clients.
+    /// - The block is marked with comments, the rest of userconfig.cfg is left alone.
+    /// - Lines added by older versions of the manager outside of the block are removed on enable and disable.
+    Userconfig {
+        action: UserconfigAction,
+        /// The game to change, defaults to the project game or the default game.
+        game: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum UserconfigAction {
+    /// Shows if the block is present.
+    Show,
+    /// Adds or updates the block.
+    Enable,
+    /// Removes the block.
+    Disable,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProjectCommands {
+    /// Bundles a project into a single archive to share it.
+    ///
+    /// - The archive contains the scripts, project.toml, the saves the scripts load, optim.rhai and a manifest.
+    /// - The manifest stores the hashes of every file and the engine and Bunnymod XT build.
+    /// - Saves are taken from whichever client has the latest one.
+    Export {
+        /// The project to export, uses the current directory if not set.
+        project_name: Option<String>,
+        /// Path of the archive, defaults to '<project>.zip'.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Imports a project from an archive made by 'project export'.
+    ///
+    /// - Places the project in the projects directory and the saves in both clients.
+    /// - Links the scripts and optim.rhai to the game directories.
+    /// - Fails if a file already exists with different contents, unless 'overwrite' is set.
+    Import {
+        bundle: PathBuf,
+        /// Imports the project under a different name.
+        #[clap(long)]
+        project_name: Option<String>,
+        /// Replaces existing files that conflict with the bundle.
+        #[clap(long)]
+        overwrite: bool,
+    },
+    /// Checks the segments declared in project.toml.
+    ///
+    /// - Every segment's script has to exist and its start save has to be in the SAVE dir of both clients.
+    /// - Warns when a segment doesn't start from the save the previous segment ends with.
+    CheckSegments {
+        /// The project to check, uses the current directory if not set.
+        project_name: Option<String>,
+    },
+    /// Records the installed engine and Bunnymod XT build as the one the project is verified on.
+    ///
+    /// - Stored as `verified_build` in project.toml.
+    /// - 'run-game' warns when the installed build differs from it.
+    SetVerifiedBuild {
+        /// The project to change, uses the current directory if not set.
+        project_name: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum StatsFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ScriptFormat {
+    Json,
+    Csv,
+}
+
+/// A frame bulk field 'hltas variants' can sweep.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum VariantField {
+    Yaw,
+    Pitch,
+    Frames,
+    Frametime,
+}
+
+/// What a frame bulk has to have to match 'hltas grep', every option given has to match.
+#[derive(Args, Debug)]
+pub struct GrepQuery {
+    /// The strafe type.
+    #[clap(long, value_parser = builder::PossibleValuesParser::new(crate::script::STRAFE_TYPES))]
+    pub strafe: Option<String>,
+    /// An automatic action, can be given more than once.
+    #[clap(long, value_parser = builder::PossibleValuesParser::new(crate::script::AUTO_ACTIONS))]
+    pub action: Vec<String>,
+    /// A held key, can be given more than once.
+    #[clap(long, value_parser = builder::PossibleValuesParser::new(crate::script::KEYS))]
+    pub key: Vec<String>,
+    /// The yaw set or strafed towards.
+    #[clap(long, allow_hyphen_values = true)]
+    pub yaw: Option<f32>,
+    /// The frame time.
+    #[clap(long)]
+    pub frametime: Option<f64>,
+    /// A console command, either the whole command or its name.
+    #[clap(long, allow_hyphen_values = true)]
+    pub command: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SeedCommands {
+    /// Shows the seeds of scripts.
+    Show { files: Vec<PathBuf> },
+    /// Sets the shared or non-shared seed, or both.
+    ///
+    /// - The seed that isn't given is kept, or set to 0 if the script had no seed.
+    /// - Needs the scripts to change, or '--all' for every script.
+    Set {
+        files: Vec<PathBuf>,
+        #[clap(long, required_unless_present = "non_shared")]
+        shared: Option<u32>,
+        #[clap(long)]
+        non_shared: Option<i64>,
+        /// Changes every script of the current project, or all projects when run from the root dir.
+        #[clap(long, conflicts_with = "files")]
+        all: bool,
+    },
+    /// Gives scripts new random shared and non-shared seeds.
+    ///
+    /// - Needs the scripts to change, or '--all' for every script.
+    Randomize {
+        files: Vec<PathBuf>,
+        /// Changes every script of the current project, or all projects when run from the root dir.
+        #[clap(long, conflicts_with = "files")]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HltasCommands {
+    /// Shows the properties of a script and a summary of its frame bulks.
+    ///
+    /// - The estimated time is the sum of the frame times of all frames.
+    /// - Console commands are listed in the order they are first used, with how many frame bulks use them.
+    Info { file: PathBuf },
+    /// Checks scripts for errors and suspicious content.
+    ///
+    /// - Checks every script of the current project, or all projects when run from the root dir, if no files are given.
+    /// - Errors are reported as `file:line:column`, the command fails if any script doesn't parse.
+    /// - Warns about frame bulks with 0 frames, conflicting strafe and key settings, unknown properties and a missing stopper at the end.
+    /// - A stopper is a last frame bulk running `stop`, `pause`, `bxt_tas_loadscript`, `load` or `quit`, or a save line.
+    Lint { files: Vec<PathBuf> },
+    /// Rewrites scripts in a canonical layout.
+    ///
+    /// - Formats every script of the current project, or all projects when run from the root dir, if no files are given.
+    /// - Properties are written in a fixed order, lines lose their indentation and every number is written in its shortest form with its exact value.
+    /// - The commands of frame bulks between blank lines are aligned into one column, the format allows no padding in the other fields.
+    /// - Comments start with `// ` and runs of blank lines between frames lines become one.
+    /// - The formatted script is parsed again and compared with the original, numbers by their exact value and commands without surrounding spaces.
+    Fmt {
+        files: Vec<PathBuf>,
+        /// Only reports scripts that aren't formatted and fails if there are any, for use in hooks.
+        #[clap(long)]
+        check: bool,
+    },
+    /// Concatenates scripts into one continuous script, such as for verifying a full run.
+    ///
+    /// - Joins the segments of the current project in order if no files are given.
+    /// - The save at the end of each script but the last, the load_command of later scripts and the stopper commands in between are dropped.
+    /// - Properties are taken from the first script, conflicting properties of later scripts are reported.
+    Join {
+        files: Vec<PathBuf>,
+        /// Path of the joined script, defaults to 'full_run.hltas' in the project when joining segments.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Splits a script into parts that continue from each other's saves.
+    ///
+    /// - The parts are written next to the script as '<script>_1.hltas', '<script>_2.hltas' and so on.
+    /// - Each part but the last ends with a save that the next part loads.
+    /// - Buttons, strafing and lgagstminspeed lines in effect at a split are repeated in the next part.
+    Split {
+        file: PathBuf,
+        /// Frame bulks to split before, counted from 0 as shown by 'diff-hltas'.
+        #[clap(long, required = true, num_args = 1..)]
+        at: Vec<usize>,
+        /// Prefix of the save names between the parts, defaults to '<script>_'.
+        #[clap(long)]
+        save_prefix: Option<String>,
+    },
+    /// Estimates the game time of scripts from the frame count and frame time of each frame bulk.
+    ///
+    /// - Times the segments of the current project in order, or all its scripts if it has no segments.
+    /// - Compares against `target_time` of the project and of each segment in project.toml.
+    /// - Shows the change since a git revision, which is the last commit by default.
+    Time {
+        files: Vec<PathBuf>,
+        /// Shows the time of every frame bulk.
+        #[clap(long)]
+        bulks: bool,
+        /// The git revision to compare against.
+        #[clap(long, conflicts_with = "log")]
+        since: Option<String>,
+        /// Shows the total time at each of the last commits and if it was a timesave or a timeloss.
+        #[clap(long)]
+        log: Option<usize>,
+    },
+    /// Finds frame bulks by what they do instead of by their text.
+    ///
+    /// - Searches every script of the current project, or all projects when run from the root dir, if no files are given.
+    /// - Prints the file, the index of the frame bulk and the frame it starts on.
+    /// - Example: `hltas grep --action jumpbug --strafe max-accel`
+    Grep {
+        files: Vec<PathBuf>,
+        #[clap(flatten)]
+        query: GrepQuery,
+        /// Prints the matches as JSON.
+        #[clap(long)]
+        json: bool,
+    },
+    /// Shows statistics of the frame bulks of scripts, with the totals of each project.
+    ///
+    /// - Frames spent in each strafe type and at each frame time, and frame bulks running console commands.
+    /// - Frame bulks and frames using each automatic action or holding the jump or duck key.
+    /// - Jumps, ducktaps and jumpbugs themselves aren't counted, how many happen depends on when the player is on the ground.
+    /// - Uses every script of the current project, or all projects when run from the root dir, if no files are given.
+    Stats {
+        files: Vec<PathBuf>,
+        #[clap(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+    /// Writes a script as JSON or CSV for editing with other tools.
+    ///
+    /// - JSON has the properties and every line with all of their fields.
+    /// - CSV has a row for every property, frame bulk and other line, frame bulks have a column for each field.
+    /// - CSV keeps every number as written, JSON holds them as 32-bit floats and rounds yaws with more than about 7 digits, so edit yaws in CSV.
+    /// - 'hltas import' turns the file back into a script.
+    Export {
+        file: PathBuf,
+        #[clap(long, value_enum, default_value_t = ScriptFormat::Json)]
+        format: ScriptFormat,
+        /// Writes to a file instead of printing.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Turns JSON or CSV made by 'hltas export' back into a script in the canonical layout.
+    ///
+    /// - The format is taken from the extension of the input if it isn't given.
+    /// - Fails without writing anything if a frame bulk isn't valid.
+    /// - Numbers from CSV are kept as written, numbers from JSON are 32-bit floats, see 'hltas export'.
+    /// - Replacing a script in a project snapshots it first, see 'hltas history'.
+    Import {
+        input: PathBuf,
+        #[clap(long, value_enum)]
+        format: Option<ScriptFormat>,
+        /// The script to write, defaults to the input with the .hltas extension.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Writes copies of a script with a frame bulk field or the seed swept over values, for brute force searches.
+    ///
+    /// - The copies are written to `.scratch/variants/<script>/` in the project and linked to the game directories.
+    /// - Running it again for the same script replaces the previous copies.
+    /// - Prints the `bxt_tas_loadscript` command of each copy to queue them.
+    /// - Example: `hltas variants route.hltas --bulk 3 --field yaw --range 90:91:0.1`
+    Variants {
+        file: PathBuf,
+        /// Index of the frame bulk to change, as shown by 'hltas grep' and 'hltas time --bulks'.
+        #[clap(long, requires = "field")]
+        bulk: Option<usize>,
+        /// The field of the frame bulk to change.
+        #[clap(long, value_enum, requires = "bulk")]
+        field: Option<VariantField>,
+        /// Values from start to end, including the end, written as '<start>:<end>:<step>'.
+        #[clap(long, requires = "field", conflicts_with = "values")]
+        range: Option<String>,
+        /// Values separated by commas.
+        #[clap(
+            long,
+            requires = "field",
+            value_delimiter = ',',
+            allow_hyphen_values = true
+        )]
+        values: Vec<String>,
+        /// Shared seeds separated by commas, the non-shared seed of the script is kept.
+        #[clap(long, value_delimiter = ',', conflicts_with = "field")]
+        seeds: Vec<u32>,
+        /// This many random shared and non-shared seeds.
+        #[clap(long, conflicts_with = "field")]
+        random_seeds: Option<usize>,
+    },
+    /// Rewrites scripts from the older forms the parser still accepts to the current format.
+    ///
+    /// - Version 1 is the only version of the HLTAS format, so there is no version to upgrade from, only older forms it tolerates.
+    /// - Frame counts of `0` or `-` become `1` and extra characters after the auto actions are removed.
+    /// - Properties that aren't read are kept, a newer Bunnymod XT may read them.
+    /// - Lists every change, only the outdated fields are rewritten and the layout is left to 'hltas fmt'.
+    /// - Fails on scripts that can't be upgraded without changing what they do, numbers keep their exact text.
+    /// - Upgrades every script of the current project, or all projects when run from the root dir, if no files are given.
+    Upgrade {
+        files: Vec<PathBuf>,
+        /// Only reports the scripts that would change and fails if there are any, for use in git hooks.
+        #[clap(long)]
+        check: bool,
+    },
+    /// Lists the snapshots 'sync' and 'link-hltas --keep-alive' took of a script, newest first.
+    ///
+    /// - Snapshots are kept in the `.history` dir of the project, independent of git.
+    /// - 'history_limit' and 'history_days' in the config set how many are kept.
+    History { file: PathBuf },
+    /// Replaces a script with one of its snapshots.
+    ///
+    /// - The script is snapshotted before it is replaced, so the restore can be undone.
+    Restore {
+        file: PathBuf,
+        /// The number of the snapshot in 'hltas history', or its name.
+        snapshot: String,
+    },
+    /// Shows which scripts make and load each save.
+    ///
+    /// - Saves are made by `save` commands, save lines, the `save` property and the binds in hltas.cfg.
+    /// - Saves are loaded by `load` commands in frame bulks and the `load_command` property.
+    /// - Warns about loaded saves that nothing makes and that aren't in the SAVE dir of either client.
+    /// - Uses the current project, or all projects when run from the root dir.
+    Saves {
+        /// Prints the graph in the DOT format of Graphviz.
+        #[clap(long)]
+        dot: bool,
+    },
+    /// Reads and changes the `seed` property of scripts.
+    ///
+    /// - Works on every script of the current project, or all projects when run from the root dir, if no files are given.
+    Seed {
+        #[clap(subcommand)]
+        command: SeedCommands,
+    },
 }
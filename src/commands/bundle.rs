@@ -0,0 +1,435 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    cfg::Cfg,
+    commands::link_hltas::{hltases_from_dir, link_hltas_once},
+    game_build::GameBuild,
+    helper,
+    project_toml::{self, ProjectToml},
+    script,
+};
+
+const MANIFEST: &str = "manifest.toml";
+const PROJECT_DIR: &str = "project";
+const SAVES_DIR: &str = "saves";
+const OPTIM_RHAI: &str = "optim.rhai";
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    project: String,
+    game: String,
+    build: GameBuild,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestFile {
+    path: String,
+    sha256: String,
+}
+
+/// Whether a name is a single plain path component, so joining it can't leave the parent dir.
+///
+/// - Separators and drive prefixes of every platform are rejected, a bundle can come from any of them.
+fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+
+    !name.contains(['/', '\\', ':'])
+        && matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+}
+
+/// Splits a path in the bundle into its top dir and the path under it.
+///
+/// - `None` if the path could leave its destination, only the project dir can have subdirs.
+fn split_bundle_path(path: &str) -> Option<(&str, &str)> {
+    let (dir, name) = match path.split_once('/') {
+        Some((dir, _)) if !is_plain_name(dir) => return None,
+        Some((dir, name)) => (dir, name),
+        None => ("", path),
+    };
+    let is_plain = name.split('/').all(is_plain_name);
+
+    (is_plain && (dir == PROJECT_DIR || !name.contains('/'))).then_some((dir, name))
+}
+
+pub fn export(project_name: &Option<String>, output: &Option<PathBuf>) -> Result<()> {
+    let cfg = helper::cfg_dir()?;
+    let cfg = Cfg::load(cfg)?;
+    let root_dir = helper::exe_dir()?;
+    let project_dir = helper::project_dir_from_name(&root_dir, &cfg, project_name)?;
+    let project_name = project_dir
+        .file_name()
+        .context("Failed to get project name")?
+        .to_string_lossy()
+        .to_string();
+    let project = ProjectToml::load_from_path(project_dir.join(project_toml::FILE_NAME))?;
+    let build = GameBuild::from_root_dir(&root_dir, &cfg)?;
+
+    info!("Exporting {project_name}");
+    for line in project.metadata() {
+        info!("  {line}");
+    }
+
+    let differences = project.verified_build_differences(&build);
+    if !differences.is_empty() {
+        warn!(
+            "The project is verified on a different {} build than the one being bundled",
+            differences.join(" and ")
+        );
+    }
+
+    // archive path to the file contents
+    let mut files = vec![(
+        format!("{PROJECT_DIR}/{}", project_toml::FILE_NAME),
+        fs::read(project_dir.join(project_toml::FILE_NAME))
+            .context("Failed to read project.toml")?,
+    )];
+    let mut saves = Vec::new();
+
+    for hltas in hltases_from_dir(&project_dir)? {
+        let contents =
+            fs::read(&hltas).with_context(|| format!("Failed to read {}", hltas.display()))?;
+
+        match script::parse(&String::from_utf8_lossy(&contents)) {
+            Ok(script) => {
+                for save in script::save_uses(&script).loaded {
+                    if !saves.contains(&save) {
+                        saves.push(save);
+                    }
+                }
+            }
+            Err(err) => warn!(
+                "Failed to parse {}, the saves it loads are not bundled: {err}",
+                hltas.display()
+            ),
+        }
+
+        // scripts keep their place in the project
+        let relative = hltas
+            .strip_prefix(&project_dir)
+            .context("Failed to get path relative to project")?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push((format!("{PROJECT_DIR}/{relative}"), contents));
+    }
+
+    // we take the latest save out of the 2 clients
+    let save_dirs = helper::save_dirs(&root_dir, &cfg, &project.game);
+    for save in saves {
+        let file_name = helper::save_file_name(&save);
+        let mut latest = None;
+
+        for save_dir in &save_dirs {
+            let path = save_dir.join(&file_name);
+            if !path.is_file() {
+                continue;
+            }
+
+            let modified = path
+                .metadata()
+                .context("Failed to get metadata")?
+                .modified()
+                .context("Failed to get modified time")?;
+
+            if latest
+                .as_ref()
+                .is_none_or(|(latest_modified, _)| modified > *latest_modified)
+            {
+                latest = Some((modified, path));
+            }
+        }
+
+        match latest {
+            Some((_, path)) => {
+                info!("Adding save {}", path.display());
+                files.push((
+                    format!("{SAVES_DIR}/{file_name}"),
+                    fs::read(&path).context("Failed to read save file")?,
+                ));
+            }
+            None => warn!("Save '{save}' is loaded by a script but doesn't exist, skipping"),
+        }
+    }
+
+    let optim_rhai = root_dir.join(OPTIM_RHAI);
+    if optim_rhai.is_file() {
+        files.push((
+            OPTIM_RHAI.to_string(),
+            fs::read(&optim_rhai).context("Failed to read optim.rhai")?,
+        ));
+    }
+
+    let manifest = Manifest {
+        project: project_name.clone(),
+        game: project.game,
+        build,
+        files: files
+            .iter()
+            .map(|(path, contents)| ManifestFile {
+                path: path.clone(),
+                sha256: helper::sha_256_hex(contents),
+            })
+            .collect(),
+    };
+    let manifest = toml::to_string(&manifest).context("Failed to serialize bundle manifest")?;
+
+    let output = match output {
+        Some(output) => output.to_owned(),
+        None => PathBuf::from(format!("{project_name}.zip")),
+    };
+
+    info!("Writing bundle to {}", output.display());
+    let file =
+        File::create(&output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    for (path, contents) in
+        std::iter::once((MANIFEST.to_string(), manifest.into_bytes())).chain(files)
+    {
+        zip.start_file(path.as_str(), options)
+            .with_context(|| format!("Failed to add {path} to bundle"))?;
+        zip.write_all(&contents)
+            .with_context(|| format!("Failed to write {path} to bundle"))?;
+    }
+
+    zip.finish().context("Failed to write bundle")?;
+
+    Ok(())
+}
+
+pub fn import<P>(bundle: P, project_name: &Option<String>, overwrite: bool) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let bundle = bundle.as_ref();
+    let cfg = helper::cfg_dir()?;
+    let cfg = Cfg::load(cfg)?;
+    let root_dir = helper::exe_dir()?;
+
+    info!("Reading bundle {}", bundle.display());
+    let file =
+        File::open(bundle).with_context(|| format!("Failed to open {}", bundle.display()))?;
+    let mut zip = ZipArchive::new(file).context("Failed to read bundle")?;
+
+    let mut read_entry = |path: &str| -> Result<Vec<u8>> {
+        let mut entry = zip
+            .by_name(path)
+            .with_context(|| format!("Bundle is missing {path}"))?;
+        let mut contents = Vec::new();
+        entry
+            .read_to_end(&mut contents)
+            .with_context(|| format!("Failed to read {path} from bundle"))?;
+
+        Ok(contents)
+    };
+
+    let manifest = String::from_utf8(read_entry(MANIFEST)?).context("Invalid bundle manifest")?;
+    let manifest: Manifest =
+        toml::from_str(&manifest).context("Failed to parse bundle manifest")?;
+
+    let project_name = project_name.as_ref().unwrap_or(&manifest.project);
+    // the names are joined into the root dir, we don't want them to write outside of it
+    if !is_plain_name(project_name) {
+        bail!("Invalid project name '{project_name}'");
+    }
+    if !is_plain_name(&manifest.game) {
+        bail!("Invalid game name '{}' in the bundle", manifest.game);
+    }
+    let project_dir = root_dir.join(&cfg.project_dir).join(project_name);
+    let save_dirs = helper::save_dirs(&root_dir, &cfg, &manifest.game);
+
+    if !root_dir
+        .join(&cfg.half_life_dir)
+        .join(&manifest.game)
+        .is_dir()
+    {
+        warn!(
+            "Game '{}' is not installed, the project won't be runnable",
+            manifest.game
+        );
+    }
+
+    // destination to the file contents
+    let mut placements = Vec::new();
+
+    for file in &manifest.files {
+        let contents = read_entry(&file.path)?;
+
+        if helper::sha_256_hex(&contents) != file.sha256 {
+            bail!("{} in the bundle doesn't match its hash", file.path);
+        }
+
+        // we don't want the bundle to write outside of the destinations
+        let Some((dir, name)) = split_bundle_path(&file.path) else {
+            bail!("Invalid path {} in the bundle", file.path);
+        };
+
+        match dir {
+            PROJECT_DIR => placements.push((project_dir.join(name), contents)),
+            SAVES_DIR => {
+                for save_dir in &save_dirs {
+                    placements.push((save_dir.join(name), contents.clone()));
+                }
+            }
+            "" if name == OPTIM_RHAI => placements.push((root_dir.join(OPTIM_RHAI), contents)),
+            _ => warn!("Unknown file {} in the bundle, skipping", file.path),
+        }
+    }
+
+    // check for conflicts before writing anything
+    let mut conflicts = Vec::new();
+    for (path, contents) in &placements {
+        if path.is_file()
+            && helper::to_hex(&helper::sha_256_file(path)?) != helper::sha_256_hex(contents)
+        {
+            conflicts.push(path.display().to_string());
+        }
+    }
+
+    if !conflicts.is_empty() && !overwrite {
+        bail!(
+            "Files already exist with different contents:\n{}\nHelp: Use '--overwrite' to replace them",
+            conflicts.join("\n")
+        );
+    }
+
+    for (path, contents) in placements {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        info!("Writing {}", path.display());
+        // writing in place keeps existing hard links intact
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    let optim_rhai = root_dir.join(OPTIM_RHAI);
+    if optim_rhai.is_file() {
+        info!("Hard-linking optim.rhai script to the Half-Life directory");
+        helper::force_link(
+            &optim_rhai,
+            root_dir.join(&cfg.half_life_dir).join(OPTIM_RHAI),
+        )
+        .context("Failed to hard-link optim.rhai script to Half-Life directory")?;
+
+        if let Some(no_client_dll_dir) = &cfg.no_client_dll_dir {
+            info!("Hard-linking optim.rhai script to the second game directory");
+            helper::force_link(
+                &optim_rhai,
+                root_dir.join(no_client_dll_dir).join(OPTIM_RHAI),
+            )
+            .context("Failed to hard-link optim.rhai script to second Half-Life directory")?;
+        }
+    }
+
+    if !project_dir.join(project_toml::FILE_NAME).is_file() {
+        info!("Creating project.toml");
+        ProjectToml {
+            game: manifest.game.clone(),
+            ..Default::default()
+        }
+        .save_to_path(project_dir.join(project_toml::FILE_NAME))?;
+    }
+
+    link_hltas_once(true, &project_dir, &cfg, false, true, false)?;
+
+    let project = ProjectToml::load_from_path(project_dir.join(project_toml::FILE_NAME))?;
+    for line in project.metadata() {
+        info!("  {line}");
+    }
+
+    let build = GameBuild::from_root_dir(&root_dir, &cfg)?;
+    let differences = build.differences(&manifest.build);
+    if !differences.is_empty() {
+        warn!(
+            "The bundle was made with a different {} build",
+            differences.join(" and ")
+        );
+    }
+
+    let differences = project.verified_build_differences(&build);
+    if !differences.is_empty() {
+        warn!(
+            "The project is verified on a different {} build than installed",
+            differences.join(" and ")
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_names() {
+        assert!(is_plain_name("route"));
+        assert!(is_plain_name("route.v2"));
+
+        for name in ["", ".", "..", "a/b", "a\\b", "../route", "..\\route"] {
+            assert!(!is_plain_name(name), "{name}");
+        }
+        for name in [
+            "/route",
+            "\\route",
+            "C:",
+            "C:route",
+            "C:\\route",
+            "\\\\server\\share",
+        ] {
+            assert!(!is_plain_name(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn bundle_paths() {
+        assert_eq!(
+            split_bundle_path("project/a.hltas"),
+            Some((PROJECT_DIR, "a.hltas"))
+        );
+        assert_eq!(
+            split_bundle_path("project/sub/a.hltas"),
+            Some((PROJECT_DIR, "sub/a.hltas"))
+        );
+        assert_eq!(split_bundle_path("saves/a.sav"), Some((SAVES_DIR, "a.sav")));
+        assert_eq!(split_bundle_path("optim.rhai"), Some(("", OPTIM_RHAI)));
+
+        for path in [
+            "project/../a.hltas",
+            "project/sub/../../a.hltas",
+            "project/./a.hltas",
+            "project//a.hltas",
+            "project/",
+            "project/..\\a.hltas",
+            "project/sub\\a.hltas",
+            "project/C:/a.hltas",
+            "project/C:\\a.hltas",
+            "saves/sub/a.sav",
+            "saves/../a.sav",
+            "/a.hltas",
+            "/etc/passwd",
+            "..\\a.hltas",
+            "C:\\a.hltas",
+            "..",
+            "",
+        ] {
+            assert_eq!(split_bundle_path(path), None, "{path}");
+        }
+    }
+}
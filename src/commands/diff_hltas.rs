@@ -0,0 +1,342 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use hltas::{
+    types::{Line, Properties},
+    HLTAS,
+};
+
+use crate::script;
+
+/// The file git passes for the missing side of an added or deleted file.
+const NULL_FILE: &str = "/dev/null";
+
+/// Above this many cells in the LCS table the changed lines are treated as replaced as a whole.
+const MAX_LCS_CELLS: usize = 16_000_000;
+
+enum Edit {
+    Equal,
+    Remove(usize),
+    Add(usize),
+}
+
+/// Index of the frame bulk on a line and the frame it starts on.
+struct Position {
+    bulk: Option<usize>,
+    frame: u64,
+}
+
+fn positions(lines: &[Line]) -> (Vec<Position>, u64) {
+    let mut positions = Vec::with_capacity(lines.len());
+    let mut bulk = 0;
+    let mut frame = 0;
+
+    for line in lines {
+        match line {
+            Line::FrameBulk(frame_bulk) => {
+                positions.push(Position {
+                    bulk: Some(bulk),
+                    frame,
+                });
+                bulk += 1;
+                frame += u64::from(frame_bulk.frame_count.get());
+            }
+            _ => positions.push(Position { bulk: None, frame }),
+        }
+    }
+
+    (positions, frame)
+}
+
+/// Line edits from `old` to `new` through the longest common subsequence.
+fn edits(old: &[Line], new: &[Line]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut edits = Vec::new();
+    edits.extend((0..prefix).map(|_| Edit::Equal));
+
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_LCS_CELLS {
+        edits.extend((0..old_mid.len()).map(|i| Edit::Remove(prefix + i)));
+        edits.extend((0..new_mid.len()).map(|i| Edit::Add(prefix + i)));
+    } else {
+        // lcs[i][j] is the common length of old_mid[i..] and new_mid[j..]
+        let width = new_mid.len() + 1;
+        let mut lcs = vec![0u32; (old_mid.len() + 1) * width];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() || j < new_mid.len() {
+            if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+                edits.push(Edit::Equal);
+                i += 1;
+                j += 1;
+            } else if j == new_mid.len()
+                || (i < old_mid.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                edits.push(Edit::Remove(prefix + i));
+                i += 1;
+            } else {
+                edits.push(Edit::Add(prefix + j));
+                j += 1;
+            }
+        }
+    }
+
+    edits.extend((0..suffix).map(|_| Edit::Equal));
+    edits
+}
+
+const NONE: &str = "(none)";
+
+fn property_changes(old: &Properties, new: &Properties) -> Vec<String> {
+    script::properties(old)
+        .into_iter()
+        .zip(script::properties(new))
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((name, old), (_, new))| {
+            format!(
+                "property {name}: {} -> {}",
+                old.as_deref().unwrap_or(NONE),
+                new.as_deref().unwrap_or(NONE)
+            )
+        })
+        .collect()
+}
+
+fn field_to_string(field: &str) -> &str {
+    if field.is_empty() {
+        NONE
+    } else {
+        field
+    }
+}
+
+fn describe(position: &Position) -> String {
+    match position.bulk {
+        Some(bulk) => format!("bulk {bulk} @ frame {}", position.frame),
+        None => format!("line @ frame {}", position.frame),
+    }
+}
+
+/// Prints the changes of a run of removed and added lines.
+///
+/// - Removed and added frame bulks are paired in order and shown as changed fields.
+fn print_hunk(
+    old: &HLTAS,
+    new: &HLTAS,
+    old_positions: &[Position],
+    new_positions: &[Position],
+    removed: &[usize],
+    added: &[usize],
+) {
+    let is_bulk = |line: &Line| matches!(line, Line::FrameBulk(_));
+    let mut removed_bulks = removed
+        .iter()
+        .filter(|i| is_bulk(&old.lines[**i]))
+        .peekable();
+    let mut added_bulks = added.iter().filter(|i| is_bulk(&new.lines[**i])).peekable();
+
+    let mut changed = Vec::new();
+    while let (Some(&&old_i), Some(&&new_i)) = (removed_bulks.peek(), added_bulks.peek()) {
+        changed.push((old_i, new_i));
+        removed_bulks.next();
+        added_bulks.next();
+    }
+
+    for &i in removed {
+        if changed.iter().any(|(old_i, _)| *old_i == i) {
+            continue;
+        }
+        println!(
+            "- {}: {}",
+            describe(&old_positions[i]),
+            script::line_to_string(&old.lines[i])
+        );
+    }
+
+    for &(old_i, new_i) in &changed {
+        let (Line::FrameBulk(old_bulk), Line::FrameBulk(new_bulk)) =
+            (&old.lines[old_i], &new.lines[new_i])
+        else {
+            continue;
+        };
+
+        let fields = script::frame_bulk_fields(old_bulk)
+            .into_iter()
+            .zip(script::frame_bulk_fields(new_bulk))
+            .zip(script::FRAME_BULK_FIELDS)
+            .filter(|((old, new), _)| old != new)
+            .map(|((old, new), name)| {
+                format!(
+                    "{name} {} -> {}",
+                    field_to_string(&old),
+                    field_to_string(&new)
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let old_position = describe(&old_positions[old_i]);
+        let new_position = describe(&new_positions[new_i]);
+        let position = if old_position == new_position {
+            new_position
+        } else {
+            format!("{new_position} (was {old_position})")
+        };
+
+        println!("~ {position}: {}", fields.join(", "));
+    }
+
+    for &i in added {
+        if changed.iter().any(|(_, new_i)| *new_i == i) {
+            continue;
+        }
+        println!(
+            "+ {}: {}",
+            describe(&new_positions[i]),
+            script::line_to_string(&new.lines[i])
+        );
+    }
+}
+
+/// Prints a script with the position of every frame bulk, so line diffs show where they are.
+pub fn textconv(args: &[String]) -> Result<()> {
+    let [path] = args else {
+        bail!("Expected a single script");
+    };
+
+    let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+    let hltas = match script::parse(&contents) {
+        Ok(hltas) => hltas,
+        Err(_) => {
+            // show the file as it is, the diff is still useful
+            print!("{contents}");
+            return Ok(());
+        }
+    };
+
+    for (name, value) in script::properties(&hltas.properties) {
+        if let Some(value) = value {
+            println!("property {name}: {value}");
+        }
+    }
+
+    let (positions, frames) = positions(&hltas.lines);
+    for (line, position) in hltas.lines.iter().zip(&positions) {
+        let line = script::line_to_string(line);
+        match position.bulk {
+            Some(_) => println!("{}: {line}", describe(position)),
+            None => println!("{line}"),
+        }
+    }
+    println!("total frames: {frames}");
+
+    Ok(())
+}
+
+fn load(path: &str) -> Result<Option<HLTAS>> {
+    if path == NULL_FILE {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+    script::parse(&contents).map(Some)
+}
+
+/// Shows the semantic differences between 2 versions of a script.
+///
+/// - Takes either `<old> <new>` or the arguments git passes to an external diff driver.
+pub fn diff_hltas(args: &[String]) -> Result<()> {
+    let (name, old_path, new_path) = match args {
+        [path] => {
+            println!("* Unmerged path {path}");
+            return Ok(());
+        }
+        [old, new] => (new.as_str(), old.as_str(), new.as_str()),
+        // path old-file old-hex old-mode new-file new-hex new-mode [new-path rename-info]
+        [path, old, _, _, new, _, _] | [path, old, _, _, new, _, _, _, _] => {
+            (path.as_str(), old.as_str(), new.as_str())
+        }
+        _ => bail!("Expected 2 scripts or the 7 arguments of a git diff driver"),
+    };
+
+    let name = Path::new(name).to_string_lossy().replace('\\', "/");
+    println!("diff-hltas a/{name} b/{name}");
+
+    let (old, new) = match (load(old_path), load(new_path)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (old, new) => {
+            // git stops showing the rest of the diff if the driver fails
+            for (side, result) in [("old", old), ("new", new)] {
+                if let Err(err) = result {
+                    println!("! {side} version failed to parse: {err:#}");
+                }
+            }
+            return Ok(());
+        }
+    };
+
+    match (&old, &new) {
+        (None, _) => println!("new script"),
+        (_, None) => println!("deleted script"),
+        _ => {}
+    }
+
+    let old = old.unwrap_or_default();
+    let new = new.unwrap_or_default();
+
+    let (old_positions, old_frames) = positions(&old.lines);
+    let (new_positions, new_frames) = positions(&new.lines);
+
+    if old_frames != new_frames {
+        println!(
+            "frames: {old_frames} -> {new_frames} ({:+})",
+            new_frames as i64 - old_frames as i64
+        );
+    }
+
+    let property_changes = property_changes(&old.properties, &new.properties);
+    for change in &property_changes {
+        println!("{change}");
+    }
+
+    let edits = edits(&old.lines, &new.lines);
+    if property_changes.is_empty() && edits.iter().all(|edit| matches!(edit, Edit::Equal)) {
+        println!("no semantic changes");
+        return Ok(());
+    }
+
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for edit in edits.iter().chain([&Edit::Equal]) {
+        match edit {
+            Edit::Remove(i) => removed.push(*i),
+            Edit::Add(i) => added.push(*i),
+            Edit::Equal => {
+                if !removed.is_empty() || !added.is_empty() {
+                    print_hunk(&old, &new, &old_positions, &new_positions, &removed, &added);
+                    removed.clear();
+                    added.clear();
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
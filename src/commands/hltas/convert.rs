@@ -0,0 +1,212 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use hltas::{
+    types::{Line, Properties},
+    HLTAS,
+};
+use log::info;
+
+use crate::{
+    cli::ScriptFormat,
+    history,
+    script::{self, TextLine},
+};
+
+const KIND_PROPERTY: &str = "property";
+const KIND_BULK: &str = "bulk";
+const KIND_LINE: &str = "line";
+
+/// Writes the properties and lines of a script as CSV rows.
+///
+/// - The fields are the text of the lines as written, so numbers keep every digit.
+fn to_csv(properties: &Properties, lines: &[TextLine]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    let mut header = vec!["kind", "text"];
+    header.extend(script::FRAME_BULK_FIELDS);
+    writer
+        .write_record(&header)
+        .context("Failed to write CSV")?;
+
+    let empty_fields = vec![String::new(); script::FRAME_BULK_FIELDS.len()];
+    for (name, value) in script::properties(properties) {
+        if let Some(value) = value {
+            let mut record = vec![KIND_PROPERTY.to_string(), format!("{name} {value}")];
+            record.extend(empty_fields.clone());
+            writer
+                .write_record(&record)
+                .context("Failed to write CSV")?;
+        }
+    }
+
+    for line in lines {
+        let record = match line.line {
+            Line::FrameBulk(_) => {
+                let mut fields = line
+                    .text
+                    .splitn(script::FRAME_BULK_FIELDS.len(), '|')
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                fields.resize(script::FRAME_BULK_FIELDS.len(), String::new());
+
+                let mut record = vec![KIND_BULK.to_string(), String::new()];
+                record.extend(fields);
+                record
+            }
+            _ => {
+                let mut record = vec![KIND_LINE.to_string(), line.text.clone()];
+                record.extend(empty_fields.clone());
+                record
+            }
+        };
+        writer
+            .write_record(&record)
+            .context("Failed to write CSV")?;
+    }
+
+    let csv = writer.into_inner().context("Failed to write CSV")?;
+    Ok(String::from_utf8_lossy(&csv).to_string())
+}
+
+/// Builds the text of a script from the rows of a CSV export.
+///
+/// - The fields are kept as written and only checked by parsing them, so numbers aren't rounded.
+fn from_csv(csv: &str) -> Result<String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+    let mut properties = String::new();
+    let mut lines = String::new();
+
+    for (i, record) in reader.records().enumerate() {
+        // the header is row 1
+        let row = i + 2;
+        let record = record.with_context(|| format!("Failed to read row {row}"))?;
+
+        match record.get(0).unwrap_or_default().trim() {
+            KIND_PROPERTY => {
+                properties.push_str(record.get(1).unwrap_or_default().trim());
+                properties.push('\n');
+            }
+            KIND_LINE => {
+                lines.push_str(record.get(1).unwrap_or_default().trim());
+                lines.push('\n');
+            }
+            KIND_BULK => {
+                let mut fields = (0..script::FRAME_BULK_FIELDS.len())
+                    .map(|field| record.get(field + 2).unwrap_or_default().trim())
+                    .collect::<Vec<_>>();
+                // a frame bulk without a command has 7 fields
+                if fields.last() == Some(&"") {
+                    fields.pop();
+                }
+
+                let line = fields.join("|");
+                if hltas::read::line(&line).is_err() {
+                    bail!("Row {row} is not a valid frame bulk: {line}");
+                }
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+            "" => {}
+            kind => bail!(
+                "Row {row} has an unknown kind '{kind}', expected '{KIND_PROPERTY}', '{KIND_BULK}' or '{KIND_LINE}'"
+            ),
+        }
+    }
+
+    Ok(format!("version 1\n{properties}frames\n{lines}"))
+}
+
+fn format_from_path(path: &Path) -> Option<ScriptFormat> {
+    match path.extension()?.to_str()? {
+        "json" => Some(ScriptFormat::Json),
+        "csv" => Some(ScriptFormat::Csv),
+        _ => None,
+    }
+}
+
+/// Writes a script as JSON or CSV, to `output` or stdout.
+///
+/// - JSON holds the numbers as the parser reads them, rounded to `f32`, CSV keeps them as written.
+pub fn export<P>(file: P, format: ScriptFormat, output: &Option<PathBuf>) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let (properties, lines) = script::load_text_lines(file)?;
+    let exported = match format {
+        ScriptFormat::Json => {
+            let hltas = HLTAS {
+                properties,
+                lines: lines.into_iter().map(|line| line.line).collect(),
+            };
+            let mut json = serde_json::to_string_pretty(&hltas).context("Failed to write JSON")?;
+            json.push('\n');
+            json
+        }
+        ScriptFormat::Csv => to_csv(&properties, &lines)?,
+    };
+
+    match output {
+        Some(output) => fs::write(output, exported)
+            .with_context(|| format!("Failed to write {}", output.display()))?,
+        None => io::stdout()
+            .write_all(exported.as_bytes())
+            .context("Failed to write to stdout")?,
+    }
+
+    Ok(())
+}
+
+/// Reads a script from JSON or CSV made by 'hltas export' and writes it in the canonical layout.
+///
+/// - The format is taken from the extension of the input if it isn't given.
+/// - Numbers from CSV keep their exact value, numbers from JSON are rounded to `f32` like the parser reads them.
+/// - An existing script in a project is snapshotted before it is replaced.
+pub fn import<P>(input: P, format: Option<ScriptFormat>, output: &Option<PathBuf>) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let input = input.as_ref();
+    let Some(format) = format.or_else(|| format_from_path(input)) else {
+        bail!(
+            "Unknown format of {}\nHelp: Use '--format json' or '--format csv'",
+            input.display()
+        );
+    };
+    let output = output
+        .clone()
+        .unwrap_or_else(|| input.with_extension("hltas"));
+
+    let contents =
+        fs::read_to_string(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    let script = match format {
+        ScriptFormat::Json => {
+            let hltas: HLTAS = serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to read {}", input.display()))?;
+            script::to_string(&hltas)?
+        }
+        ScriptFormat::Csv => from_csv(&contents)?,
+    };
+    let script = script::format(&script).context("The imported script is not valid")?;
+
+    if output.is_file() {
+        if let Ok(project_dir) = history::project_dir_of(&output) {
+            let output = output.canonicalize().context("Failed to find script")?;
+            if let Some(snapshot) = history::snapshot(&project_dir, &output)? {
+                info!("Saved the current script as {}", snapshot.display());
+            }
+        }
+    }
+
+    info!("Writing {}", output.display());
+    // writing in place keeps the hard links to the game directories
+    fs::write(&output, script).with_context(|| format!("Failed to write {}", output.display()))?;
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::{env::current_dir, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use log::{error, info};
+
+use crate::script;
+
+use super::script_files;
+
+/// Rewrites the scripts in the canonical layout, or only reports the ones that aren't with `check`.
+pub fn fmt(files: &[PathBuf], check: bool) -> Result<()> {
+    let current_dir = current_dir().context("Failed to get current directory")?;
+    let files = script_files(files)?;
+
+    let mut unformatted = 0;
+    let mut failed = 0;
+
+    for file in &files {
+        let display = file.strip_prefix(&current_dir).unwrap_or(file).display();
+        let contents = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+
+        let formatted = match script::format(&contents) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                error!("{display}: {err:#}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        if formatted == contents {
+            continue;
+        }
+
+        unformatted += 1;
+        if check {
+            info!("{display} is not formatted");
+        } else {
+            info!("Formatting {display}");
+            // writing in place keeps the hard links to the game directories
+            fs::write(file, formatted)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} scripts couldn't be formatted");
+    }
+    if check && unformatted > 0 {
+        bail!("{unformatted} scripts are not formatted\nHelp: Run 'hltas fmt' to format them");
+    }
+
+    Ok(())
+}
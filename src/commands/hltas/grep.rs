@@ -0,0 +1,137 @@
+use std::{env::current_dir, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use hltas::types::FrameBulk;
+use log::{error, info};
+use serde::Serialize;
+
+use crate::{cli::GrepQuery, script};
+
+use super::script_files;
+
+/// A frame bulk that matched the query.
+#[derive(Serialize)]
+struct Match {
+    file: String,
+    bulk: usize,
+    frame: u64,
+    line: String,
+}
+
+impl GrepQuery {
+    fn is_empty(&self) -> bool {
+        self.strafe.is_none()
+            && self.action.is_empty()
+            && self.key.is_empty()
+            && self.yaw.is_none()
+            && self.frametime.is_none()
+            && self.command.is_none()
+    }
+
+    /// If the frame bulk matches every part of the query.
+    fn matches(&self, frame_bulk: &FrameBulk) -> bool {
+        if let Some(strafe) = &self.strafe {
+            if script::strafe_type(frame_bulk) != Some(strafe.as_str()) {
+                return false;
+            }
+        }
+
+        let auto_actions = script::auto_actions(frame_bulk);
+        if !self
+            .action
+            .iter()
+            .all(|action| auto_actions.contains(&action.as_str()))
+        {
+            return false;
+        }
+
+        let keys = script::keys(frame_bulk);
+        if !self.key.iter().all(|key| keys.contains(&key.as_str())) {
+            return false;
+        }
+
+        if let Some(yaw) = self.yaw {
+            if !script::yaw(frame_bulk).is_some_and(|bulk_yaw| (bulk_yaw - yaw).abs() < 1e-4) {
+                return false;
+            }
+        }
+
+        if let Some(frametime) = self.frametime {
+            if (script::frame_time(frame_bulk) - frametime).abs() > 1e-9 {
+                return false;
+            }
+        }
+
+        if let Some(command) = &self.command {
+            // either the whole command or its name, so `+attack` finds `+attack` and `wait` finds `wait 5`
+            if !script::console_commands(frame_bulk)
+                .iter()
+                .any(|c| *c == command || c.split_whitespace().next() == Some(command.as_str()))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Finds the frame bulks that match the query in the scripts.
+///
+/// - Searches every script of the current project, or all projects when run from the root dir, if no files are given.
+/// - Prints `file:bulk N @ frame F: line`, or a JSON array of the matches with `json`.
+pub fn grep(files: &[PathBuf], query: &GrepQuery, json: bool) -> Result<()> {
+    if query.is_empty() {
+        bail!("Nothing to search for\nHelp: Use 'hltas grep --help' to see the options");
+    }
+
+    let current_dir = current_dir().context("Failed to get current directory")?;
+    let mut matches = Vec::new();
+
+    for file in script_files(files)? {
+        let display = file
+            .strip_prefix(&current_dir)
+            .unwrap_or(&file)
+            .display()
+            .to_string();
+
+        let hltas = match script::load(&file) {
+            Ok(hltas) => hltas,
+            Err(err) => {
+                error!("{display}: {err:#}");
+                continue;
+            }
+        };
+
+        let mut frame = 0;
+        for (bulk, frame_bulk) in hltas.frame_bulks().enumerate() {
+            if query.matches(frame_bulk) {
+                matches.push(Match {
+                    file: display.clone(),
+                    bulk,
+                    frame,
+                    line: script::frame_bulk_to_string(frame_bulk),
+                });
+            }
+            frame += u64::from(frame_bulk.frame_count.get());
+        }
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&matches).context("Failed to write JSON")?
+        );
+        return Ok(());
+    }
+
+    for found in &matches {
+        println!(
+            "{}:bulk {} @ frame {}: {}",
+            found.file, found.bulk, found.frame, found.line
+        );
+    }
+    info!("{} matching frame bulks", matches.len());
+
+    Ok(())
+}
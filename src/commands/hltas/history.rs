@@ -0,0 +1,88 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use log::info;
+
+use crate::{helper, history, script};
+
+/// Lists the snapshots of a script, newest first, with a summary of each.
+pub fn history<P>(file: P) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let file = file.as_ref();
+    let project_dir = history::project_dir_of(file)?;
+    let file = file.canonicalize().context("Failed to find script")?;
+    let snapshots = history::snapshots(&project_dir, &file)?;
+
+    if snapshots.is_empty() {
+        info!("{} has no snapshots", file.display());
+        return Ok(());
+    }
+
+    let current = fs::read(&file).ok();
+    for (i, snapshot) in snapshots.iter().enumerate() {
+        let contents = fs::read_to_string(&snapshot.path)
+            .with_context(|| format!("Failed to read {}", snapshot.path.display()))?;
+        let summary = match script::parse(&contents) {
+            Ok(hltas) => format!(
+                "{} frames, {}",
+                script::total_frames(&hltas),
+                helper::format_time(script::estimated_time(&hltas))
+            ),
+            Err(_) => "doesn't parse".to_string(),
+        };
+        let is_current = if current.as_deref() == Some(contents.as_bytes()) {
+            ", current"
+        } else {
+            ""
+        };
+
+        info!(
+            "{} {} ({}{is_current}): {summary}",
+            i + 1,
+            snapshot.name,
+            history::format_age(snapshot.time)
+        );
+    }
+
+    Ok(())
+}
+
+/// Replaces a script with one of its snapshots, by number from 'hltas history' or by name.
+///
+/// - The script is snapshotted first, so the restore can be undone.
+pub fn restore<P>(file: P, snapshot: &str) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let file = file.as_ref();
+    let project_dir = history::project_dir_of(file)?;
+    let file = file.canonicalize().context("Failed to find script")?;
+    let snapshots = history::snapshots(&project_dir, &file)?;
+
+    let name = snapshot.trim_end_matches(".hltas");
+    let found = match name.parse::<usize>() {
+        Ok(number) => number.checked_sub(1).and_then(|i| snapshots.get(i)),
+        Err(_) => snapshots.iter().find(|snapshot| snapshot.name == name),
+    };
+    let Some(found) = found else {
+        bail!(
+            "Snapshot '{snapshot}' of {} not found\nHelp: Use 'hltas history' to list the snapshots",
+            file.display()
+        );
+    };
+
+    let contents = fs::read(&found.path)
+        .with_context(|| format!("Failed to read {}", found.path.display()))?;
+
+    if let Some(backup) = history::snapshot(&project_dir, &file)? {
+        info!("Saved the current script as {}", backup.display());
+    }
+
+    info!("Restoring {} from {}", file.display(), found.name);
+    // writing in place keeps the hard links to the game directories
+    fs::write(&file, contents).with_context(|| format!("Failed to write {}", file.display()))?;
+
+    Ok(())
+}
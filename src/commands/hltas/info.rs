@@ -0,0 +1,59 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use log::info;
+
+use crate::{helper, script};
+
+/// Prints the properties and a summary of the frame bulks of a script.
+pub fn info<P>(file: P) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let file = file.as_ref();
+    let contents =
+        fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let hltas =
+        script::parse(&contents).with_context(|| format!("Failed to parse {}", file.display()))?;
+
+    match script::version(&contents) {
+        Some(version) => info!("version: {version}"),
+        None => info!("version: unknown"),
+    }
+
+    for (name, value) in script::properties(&hltas.properties) {
+        if let Some(value) = value {
+            info!("{name}: {value}");
+        }
+    }
+
+    let estimated_time = script::estimated_time(&hltas);
+    info!("frame bulks: {}", hltas.frame_bulks().count());
+    info!("total frames: {}", script::total_frames(&hltas));
+    info!(
+        "estimated time: {} ({estimated_time:.3}s)",
+        helper::format_time(estimated_time)
+    );
+
+    // in order of first use
+    let mut console_commands: Vec<(&str, usize)> = Vec::new();
+    for frame_bulk in hltas.frame_bulks() {
+        for command in script::console_commands(frame_bulk) {
+            match console_commands.iter_mut().find(|(c, _)| *c == command) {
+                Some((_, count)) => *count += 1,
+                None => console_commands.push((command, 1)),
+            }
+        }
+    }
+
+    if console_commands.is_empty() {
+        info!("console commands: none");
+    } else {
+        info!("console commands:");
+        for (command, count) in console_commands {
+            info!("  {command} (x{count})");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use std::{
+    env::current_dir,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use hltas::types::{FrameBulk, Line, Properties};
+use log::{info, warn};
+
+use crate::{
+    lint::STOPPER_COMMANDS,
+    project_toml::{self, ProjectToml},
+    script::{self, TextLine},
+};
+
+/// Default name of the joined script in the project dir.
+const DEFAULT_OUTPUT: &str = "full_run.hltas";
+
+/// Removes the commands that stop the script or load the next one from the last frame bulk.
+fn remove_stopper(lines: &mut [TextLine], name: &str) {
+    let Some(frame_bulk) = lines
+        .iter_mut()
+        .rfind(|line| matches!(line.line, Line::FrameBulk(_)))
+    else {
+        return;
+    };
+    let Line::FrameBulk(FrameBulk {
+        console_command: Some(console_command),
+        ..
+    }) = &frame_bulk.line
+    else {
+        return;
+    };
+
+    let (stoppers, kept): (Vec<_>, Vec<_>) = console_command
+        .split(';')
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .partition(|command| {
+            let command = command.split_whitespace().next().unwrap_or_default();
+            STOPPER_COMMANDS.contains(&command)
+        });
+
+    if stoppers.is_empty() {
+        return;
+    }
+
+    info!("Dropping '{}' at the end of {name}", stoppers.join(";"));
+    let kept = (!kept.is_empty()).then(|| kept.join(";"));
+    frame_bulk.set_console_command(kept);
+}
+
+/// Warns about a property of a later segment that differs from the joined script.
+fn reconcile<T: PartialEq + std::fmt::Debug>(
+    property: &str,
+    joined: &mut Option<T>,
+    segment: Option<T>,
+    name: &str,
+) {
+    match (&joined, segment) {
+        (None, Some(value)) => *joined = Some(value),
+        (Some(joined), Some(value)) if *joined != value => warn!(
+            "{name} sets {property} to {value:?} but the run uses {joined:?}, keeping the first"
+        ),
+        _ => {}
+    }
+}
+
+/// Concatenates scripts into one continuous script.
+///
+/// - Uses the segments of the current project if no files are given.
+/// - The saves and loads between the scripts and the stopper at the end of each script but the last are dropped.
+/// - Properties come from the first script, later scripts can only add properties that are missing.
+/// - The lines are copied as written, the parser would round their numbers to `f32`.
+pub fn join(files: &[PathBuf], output: &Option<PathBuf>) -> Result<()> {
+    let current_dir = current_dir().context("Failed to get current directory")?;
+
+    let (files, output) = if files.is_empty() {
+        let project_toml = current_dir.join(project_toml::FILE_NAME);
+        if !project_toml.is_file() {
+            bail!("No scripts given\nHelp: Pass the scripts or run from a project with segments");
+        }
+
+        let project = ProjectToml::load_from_path(project_toml)?;
+        if project.segments.is_empty() {
+            bail!("The project has no segments\nHelp: Add [[segments]] to project.toml");
+        }
+
+        let files = project
+            .segments
+            .iter()
+            .map(|segment| current_dir.join(&segment.script))
+            .collect::<Vec<_>>();
+        let output = output
+            .clone()
+            .unwrap_or_else(|| current_dir.join(DEFAULT_OUTPUT));
+
+        (files, output)
+    } else {
+        let output = output.clone().context("Joining scripts needs '--output'")?;
+
+        (files.to_vec(), output)
+    };
+
+    if files.iter().any(|file| same_path(file, &output)) {
+        bail!(
+            "The output {} is one of the joined scripts",
+            output.display()
+        );
+    }
+
+    let mut joined_properties = Properties::default();
+    let mut joined_lines: Vec<TextLine> = Vec::new();
+    let last = files.len() - 1;
+
+    for (i, file) in files.iter().enumerate() {
+        let (properties, mut lines) = script::load_text_lines(file)?;
+        let name = file
+            .strip_prefix(&current_dir)
+            .unwrap_or(file)
+            .display()
+            .to_string();
+
+        if i < last {
+            // the last save line is where the next script picks up
+            if let Some(save) = lines
+                .iter()
+                .rposition(|line| matches!(line.line, Line::Save(_)))
+            {
+                if lines[save + 1..]
+                    .iter()
+                    .all(|line| !matches!(line.line, Line::FrameBulk(_)))
+                {
+                    info!("Dropping the save at the end of {name}");
+                    lines.remove(save);
+                }
+            }
+            remove_stopper(&mut lines, &name);
+        }
+
+        if i == 0 {
+            joined_properties = properties;
+        } else {
+            if let Some(load_command) = properties.load_command {
+                info!("Dropping load_command '{load_command}' of {name}");
+            }
+
+            reconcile("demo", &mut joined_properties.demo, properties.demo, &name);
+            reconcile("save", &mut joined_properties.save, properties.save, &name);
+            reconcile(
+                "frametime0ms",
+                &mut joined_properties.frametime_0ms,
+                properties.frametime_0ms,
+                &name,
+            );
+            reconcile(
+                "seed",
+                &mut joined_properties.seeds,
+                properties.seeds,
+                &name,
+            );
+            reconcile(
+                "hlstrafe_version",
+                &mut joined_properties.hlstrafe_version,
+                properties.hlstrafe_version,
+                &name,
+            );
+        }
+
+        // state that a split repeated at the start is already in effect
+        let state = script::state_at(&joined_lines, joined_lines.len());
+        let first_frame_bulk = lines
+            .iter()
+            .position(|line| matches!(line.line, Line::FrameBulk(_)))
+            .unwrap_or(lines.len());
+
+        joined_lines.push(TextLine::new(Line::Comment(format!(" {name}"))));
+        joined_lines.extend(lines.into_iter().enumerate().filter_map(|(j, line)| {
+            let in_effect = state.iter().any(|state| state.text == line.text);
+            (j >= first_frame_bulk || !in_effect).then_some(line)
+        }));
+    }
+
+    info!("Writing {}", output.display());
+    script::save_text_lines(&output, &joined_properties, &joined_lines)
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
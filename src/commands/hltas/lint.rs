@@ -0,0 +1,50 @@
+use std::{env::current_dir, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use log::{error, info, warn};
+
+use crate::lint::{self, Severity};
+
+use super::script_files;
+
+/// Lints the scripts, or every script of the current project or all projects.
+pub fn lint(files: &[PathBuf]) -> Result<()> {
+    let current_dir = current_dir().context("Failed to get current directory")?;
+
+    let files = script_files(files)?;
+
+    let mut broken = 0;
+    let mut warnings = 0;
+
+    for file in &files {
+        let contents = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let diagnostics = lint::lint(&contents);
+        let display = file.strip_prefix(&current_dir).unwrap_or(file).display();
+
+        for diagnostic in &diagnostics {
+            match diagnostic.severity {
+                Severity::Error => error!("{display}:{diagnostic}"),
+                Severity::Warning => {
+                    warnings += 1;
+                    warn!("{display}:{diagnostic}");
+                }
+            }
+        }
+
+        if lint::has_errors(&diagnostics) {
+            broken += 1;
+        }
+    }
+
+    info!(
+        "Linted {} scripts, {broken} with errors, {warnings} warnings",
+        files.len()
+    );
+
+    if broken > 0 {
+        bail!("{broken} scripts failed to parse");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+pub mod convert;
+pub mod fmt;
+pub mod grep;
+pub mod history;
+pub mod info;
+pub mod join;
+pub mod lint;
+pub mod saves;
+pub mod seed;
+pub mod split;
+pub mod stats;
+pub mod time;
+pub mod upgrade;
+pub mod variants;
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::{commands::link_hltas::hltases_from_dir, helper};
+
+/// The given scripts, or every script of the current project or all projects if none are given.
+fn script_files(files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    if !files.is_empty() {
+        return Ok(files.to_vec());
+    }
+
+    let mut files = Vec::new();
+    for project_dir in helper::current_project_dirs()? {
+        files.extend(hltases_from_dir(&project_dir)?);
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,237 @@
+use std::{
+    env::current_dir,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+
+use crate::{
+    cfg::{self, Cfg},
+    commands::link_hltas::hltases_from_dir,
+    helper,
+    project_toml::{self, ProjectToml},
+    script,
+};
+
+/// Name of the producer for saves made by the binds in hltas.cfg.
+const HLTAS_CFG: &str = "hltas.cfg";
+
+/// A save with the scripts that make and load it.
+struct SaveNode {
+    game: String,
+    name: String,
+    made_by: Vec<String>,
+    loaded_by: Vec<String>,
+    /// If the save is in the SAVE dir of either client.
+    exists: bool,
+}
+
+impl SaveNode {
+    fn is_missing(&self) -> bool {
+        self.made_by.is_empty() && !self.exists
+    }
+}
+
+/// Saves made by the commands in a cfg, such as `bind f7 "save quick"`.
+fn cfg_saves(contents: &str) -> Vec<String> {
+    let mut saves = Vec::new();
+
+    for line in contents.lines() {
+        let line = line.split("//").next().unwrap_or_default();
+
+        for command in line.split([';', '"']) {
+            if let Some((true, name)) = script::save_command(command) {
+                if !saves.contains(&name) {
+                    saves.push(name);
+                }
+            }
+        }
+    }
+
+    saves
+}
+
+fn push_unique(list: &mut Vec<String>, item: &str) {
+    if !list.iter().any(|i| i == item) {
+        list.push(item.to_string());
+    }
+}
+
+/// Index of the node of a save, adding it if it isn't in the graph yet.
+fn node_index(
+    nodes: &mut Vec<SaveNode>,
+    root_dir: &Path,
+    cfg: &Cfg,
+    game: &str,
+    name: &str,
+) -> usize {
+    if let Some(i) = nodes
+        .iter()
+        .position(|node| node.game == game && node.name == name)
+    {
+        return i;
+    }
+
+    let file_name = helper::save_file_name(name);
+    nodes.push(SaveNode {
+        game: game.to_string(),
+        name: name.to_string(),
+        made_by: Vec::new(),
+        loaded_by: Vec::new(),
+        exists: helper::save_dirs(root_dir, cfg, game)
+            .iter()
+            .any(|save_dir| save_dir.join(&file_name).is_file()),
+    });
+
+    nodes.len() - 1
+}
+
+/// Builds the graph of saves made and loaded by the scripts of the projects.
+fn save_graph(root_dir: &Path, cfg: &Cfg, project_dirs: &[PathBuf]) -> Result<Vec<SaveNode>> {
+    let current_dir = current_dir().context("Failed to get current directory")?;
+    let mut nodes = Vec::new();
+
+    for project_dir in project_dirs {
+        let project = ProjectToml::load_from_path(project_dir.join(project_toml::FILE_NAME))?;
+
+        for file in hltases_from_dir(project_dir)? {
+            let display = file
+                .strip_prefix(&current_dir)
+                .unwrap_or(&file)
+                .display()
+                .to_string();
+
+            let hltas = match script::load(&file) {
+                Ok(hltas) => hltas,
+                Err(err) => {
+                    warn!("Skipping {display}: {err:#}");
+                    continue;
+                }
+            };
+
+            let uses = script::save_uses(&hltas);
+            for name in &uses.made {
+                let i = node_index(&mut nodes, root_dir, cfg, &project.game, name);
+                push_unique(&mut nodes[i].made_by, &display);
+            }
+            for name in &uses.loaded {
+                let i = node_index(&mut nodes, root_dir, cfg, &project.game, name);
+                push_unique(&mut nodes[i].loaded_by, &display);
+            }
+        }
+    }
+
+    // the binds make saves by hand, only the ones scripts use are part of the graph
+    let hltas_cfg = cfg
+        .cfgs_dir
+        .as_ref()
+        .map(|cfgs_dir| root_dir.join(cfgs_dir).join(HLTAS_CFG))
+        .filter(|hltas_cfg| hltas_cfg.is_file());
+    if let Some(hltas_cfg) = hltas_cfg {
+        let contents = fs::read_to_string(&hltas_cfg)
+            .with_context(|| format!("Failed to read {}", hltas_cfg.display()))?;
+        let cfg_saves = cfg_saves(&contents);
+
+        for node in nodes
+            .iter_mut()
+            .filter(|node| cfg_saves.contains(&node.name))
+        {
+            push_unique(&mut node.made_by, HLTAS_CFG);
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// Quotes a DOT identifier.
+fn dot_id(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn print_dot(nodes: &[SaveNode], multiple_games: bool) {
+    println!("digraph saves {{");
+    println!("    rankdir=LR;");
+
+    let mut scripts = Vec::new();
+    for node in nodes {
+        for script in node.made_by.iter().chain(&node.loaded_by) {
+            push_unique(&mut scripts, script);
+        }
+    }
+    for script in &scripts {
+        println!("    {} [shape=box];", dot_id(script));
+    }
+
+    for node in nodes {
+        let id = dot_id(&format!("save:{}:{}", node.game, node.name));
+        let label = save_label(node, multiple_games);
+        let color = if node.is_missing() { ", color=red" } else { "" };
+        println!("    {id} [label={}, shape=ellipse{color}];", dot_id(&label));
+
+        for script in &node.made_by {
+            println!("    {} -> {id};", dot_id(script));
+        }
+        for script in &node.loaded_by {
+            println!("    {id} -> {};", dot_id(script));
+        }
+    }
+
+    println!("}}");
+}
+
+fn save_label(node: &SaveNode, multiple_games: bool) -> String {
+    if multiple_games {
+        format!("{}/{}", node.game, node.name)
+    } else {
+        node.name.clone()
+    }
+}
+
+fn print_text(nodes: &[SaveNode], multiple_games: bool) {
+    for node in nodes {
+        let exists = if node.exists { " (in SAVE)" } else { "" };
+        info!("{}{exists}", save_label(node, multiple_games));
+
+        if !node.made_by.is_empty() {
+            info!("  made by {}", node.made_by.join(", "));
+        }
+        if !node.loaded_by.is_empty() {
+            info!("  loaded by {}", node.loaded_by.join(", "));
+        }
+    }
+}
+
+/// Shows which scripts make and load each save, and reports loaded saves that nothing makes.
+///
+/// - Uses the current project, or all projects when run from the root dir.
+/// - A save is missing if no script or hltas.cfg bind makes it and it isn't in the SAVE dir of either client.
+pub fn saves(dot: bool) -> Result<()> {
+    let root_dir = helper::try_root_dir()?;
+    let cfg = Cfg::load(root_dir.join(cfg::cfg_file_name())).context("Failed to load cfg")?;
+    let project_dirs = helper::current_project_dirs()?;
+
+    if project_dirs.is_empty() {
+        bail!("No projects found");
+    }
+
+    let nodes = save_graph(&root_dir, &cfg, &project_dirs)?;
+    let multiple_games = nodes.iter().any(|node| node.game != nodes[0].game);
+
+    if dot {
+        print_dot(&nodes, multiple_games);
+    } else {
+        print_text(&nodes, multiple_games);
+    }
+
+    for node in nodes.iter().filter(|node| node.is_missing()) {
+        warn!(
+            "Save '{}' is loaded by {} but no script makes it and it isn't in either SAVE dir",
+            save_label(node, multiple_games),
+            node.loaded_by.join(", ")
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::{env::current_dir, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use hltas::types::Seeds;
+use log::{error, info};
+
+use crate::{helper, script};
+
+use super::script_files;
+
+/// Shows the seeds of the scripts.
+pub fn show(files: &[PathBuf]) -> Result<()> {
+    let current_dir = current_dir().context("Failed to get current directory")?;
+
+    for file in script_files(files)? {
+        let display = file.strip_prefix(&current_dir).unwrap_or(&file).display();
+
+        match script::load(&file) {
+            Ok(hltas) => match &hltas.properties.seeds {
+                Some(seeds) => info!("{display}: {}", script::seeds_to_string(seeds)),
+                None => info!("{display}: no seed"),
+            },
+            Err(err) => error!("{display}: {err:#}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Rewrites the seed of each script with the seeds `new_seeds` gives from its current ones.
+///
+/// - Every script of the current project or all projects is only changed with `all`, so it can't happen by forgetting the files.
+fn update_seeds<F>(files: &[PathBuf], all: bool, mut new_seeds: F) -> Result<()>
+where
+    F: FnMut(Option<Seeds>) -> Seeds,
+{
+    if files.is_empty() && !all {
+        bail!("No scripts given\nHelp: Pass the scripts to change, or use '--all' to change every script");
+    }
+
+    let current_dir = current_dir().context("Failed to get current directory")?;
+    let mut failed = 0;
+
+    for file in script_files(files)? {
+        let display = file.strip_prefix(&current_dir).unwrap_or(&file).display();
+        let contents = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+
+        let result = script::parse(&contents).and_then(|hltas| {
+            let seeds = new_seeds(hltas.properties.seeds);
+            let seeds = script::seeds_to_string(&seeds);
+            script::set_property(&contents, "seed", Some(&seeds)).map(|contents| (contents, seeds))
+        });
+
+        match result {
+            Ok((modified, seeds)) => {
+                info!("{display}: seed {seeds}");
+                // writing in place keeps the hard links to the game directories
+                fs::write(&file, modified)
+                    .with_context(|| format!("Failed to write {}", file.display()))?;
+            }
+            Err(err) => {
+                error!("{display}: {err:#}");
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("Failed to set the seed of {failed} scripts");
+    }
+
+    Ok(())
+}
+
+/// Sets the shared or non-shared seed of the scripts, the other one is kept or `0` if there was no seed.
+pub fn set(
+    files: &[PathBuf],
+    all: bool,
+    shared: Option<u32>,
+    non_shared: Option<i64>,
+) -> Result<()> {
+    update_seeds(files, all, |seeds| {
+        let seeds = seeds.unwrap_or(Seeds {
+            shared: 0,
+            non_shared: 0,
+        });
+
+        Seeds {
+            shared: shared.unwrap_or(seeds.shared),
+            non_shared: non_shared.unwrap_or(seeds.non_shared),
+        }
+    })
+}
+
+pub fn random_seeds() -> Seeds {
+    Seeds {
+        shared: helper::random_u64() as u32,
+        // a positive 32 bit number like the shared seed
+        non_shared: i64::from(helper::random_u64() as u32),
+    }
+}
+
+/// Gives each script new random seeds.
+pub fn randomize(files: &[PathBuf], all: bool) -> Result<()> {
+    update_seeds(files, all, |_| random_seeds())
+}
@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use hltas::types::{Line, Properties};
+use log::info;
+
+use crate::script::{self, TextLine};
+
+/// Splits a script before the given frame bulks into scripts that continue from each other's saves.
+///
+/// - Frame bulks are counted from 0, the same as in 'diff-hltas'.
+/// - Each part but the last ends with a save line that the next part loads with `load_command`.
+/// - Buttons, strafing and lgagstminspeed lines that are in effect at the split are repeated in the next part.
+/// - The lines are copied as written, the parser would round their numbers to `f32`.
+pub fn split<P>(file: P, at: &[usize], save_prefix: &Option<String>) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let file = file.as_ref();
+    let (properties, lines) = script::load_text_lines(file)?;
+
+    let stem = file
+        .file_stem()
+        .context("Failed to get script name")?
+        .to_string_lossy()
+        .to_string();
+    let save_prefix = save_prefix.clone().unwrap_or_else(|| format!("{stem}_"));
+
+    // line index of every frame bulk
+    let bulk_lines = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| matches!(line.line, Line::FrameBulk(_)))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    let mut at = at.to_vec();
+    at.sort_unstable();
+    at.dedup();
+
+    let mut splits = Vec::new();
+    for bulk in at {
+        if bulk == 0 || bulk >= bulk_lines.len() {
+            bail!(
+                "Can't split before frame bulk {bulk}, the script has frame bulks 0 to {}",
+                bulk_lines.len().saturating_sub(1)
+            );
+        }
+
+        // comments right before the frame bulk go with it
+        let mut split = bulk_lines[bulk];
+        while split > 0 && matches!(lines[split - 1].line, Line::Comment(_)) {
+            split -= 1;
+        }
+        splits.push(split);
+    }
+
+    if splits.is_empty() {
+        bail!("No frame bulks to split at");
+    }
+
+    let mut starts = vec![0];
+    starts.extend(&splits);
+    let mut ends = splits.clone();
+    ends.push(lines.len());
+
+    let parts = starts.len();
+    let mut outputs: Vec<(PathBuf, Properties, Vec<TextLine>)> = Vec::new();
+
+    for (i, (start, end)) in starts.into_iter().zip(ends).enumerate() {
+        let mut part_properties = properties.clone();
+        let mut part_lines = Vec::new();
+
+        if i > 0 {
+            let previous_save = format!("{save_prefix}{i}");
+            part_properties.demo = None;
+            part_properties.load_command = Some(format!("load {previous_save}"));
+            part_lines.extend(script::state_at(&lines, start));
+        }
+
+        part_lines.extend_from_slice(&lines[start..end]);
+
+        if i + 1 < parts {
+            part_lines.push(TextLine::new(Line::Save(format!("{save_prefix}{}", i + 1))));
+        }
+
+        let output = file.with_file_name(format!("{stem}_{}.hltas", i + 1));
+        outputs.push((output, part_properties, part_lines));
+    }
+
+    for (output, _, _) in &outputs {
+        if output.exists() {
+            bail!("{} already exists", output.display());
+        }
+    }
+
+    for (output, properties, part_lines) in outputs {
+        info!("Writing {}", output.display());
+        script::save_text_lines(&output, &properties, &part_lines)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::{env::current_dir, io, path::PathBuf};
+
+use anyhow::{Context, Result};
+use log::{error, info};
+use serde::Serialize;
+
+use crate::{
+    cli::StatsFormat, commands::link_hltas::hltases_from_dir, helper, script, stats::Stats,
+};
+
+#[derive(Serialize)]
+struct ScriptStats {
+    file: String,
+    group: String,
+    stats: Stats,
+}
+
+#[derive(Serialize)]
+struct GroupStats {
+    group: String,
+    stats: Stats,
+}
+
+#[derive(Serialize)]
+struct Report {
+    scripts: Vec<ScriptStats>,
+    totals: Vec<GroupStats>,
+}
+
+/// The scripts grouped by project, or the given files as a single group.
+fn groups(files: &[PathBuf]) -> Result<Vec<(String, Vec<PathBuf>)>> {
+    if !files.is_empty() {
+        return Ok(vec![("total".to_string(), files.to_vec())]);
+    }
+
+    let mut groups = Vec::new();
+    for project_dir in helper::current_project_dirs()? {
+        let name = project_dir
+            .file_name()
+            .context("Failed to get project name")?
+            .to_string_lossy()
+            .to_string();
+        groups.push((name, hltases_from_dir(&project_dir)?));
+    }
+
+    Ok(groups)
+}
+
+fn report(files: &[PathBuf]) -> Result<Report> {
+    let current_dir = current_dir().context("Failed to get current directory")?;
+    let mut report = Report {
+        scripts: Vec::new(),
+        totals: Vec::new(),
+    };
+
+    for (group, files) in groups(files)? {
+        let mut total = Stats::default();
+
+        for file in files {
+            let display = file
+                .strip_prefix(&current_dir)
+                .unwrap_or(&file)
+                .display()
+                .to_string();
+
+            let hltas = match script::load(&file) {
+                Ok(hltas) => hltas,
+                Err(err) => {
+                    error!("{display}: {err:#}");
+                    continue;
+                }
+            };
+
+            let stats = Stats::from_hltas(&hltas);
+            total.add(&stats);
+            report.scripts.push(ScriptStats {
+                file: display,
+                group: group.clone(),
+                stats,
+            });
+        }
+
+        report.totals.push(GroupStats {
+            group,
+            stats: total,
+        });
+    }
+
+    Ok(report)
+}
+
+fn print_table(name: &str, stats: &Stats) {
+    info!("{name}");
+    for (stat, key, value) in stats.rows() {
+        info!("  {stat:<22} {key:<20} {value:>8}");
+    }
+}
+
+/// Shows statistics of the frame bulks of each script and the totals of each project.
+///
+/// - Uses every script of the current project, or all projects when run from the root dir, if no files are given.
+/// - CSV has a row for every stat of every script and total, JSON has the scripts and the totals.
+pub fn stats(files: &[PathBuf], format: StatsFormat) -> Result<()> {
+    let report = report(files)?;
+
+    match format {
+        StatsFormat::Table => {
+            for script in &report.scripts {
+                print_table(&script.file, &script.stats);
+            }
+            for total in &report.totals {
+                print_table(&format!("{} (total)", total.group), &total.stats);
+            }
+        }
+        StatsFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout());
+            writer
+                .write_record(["scope", "name", "stat", "key", "value"])
+                .context("Failed to write CSV")?;
+
+            let scripts = report
+                .scripts
+                .iter()
+                .map(|script| ("script", &script.file, &script.stats));
+            let totals = report
+                .totals
+                .iter()
+                .map(|total| ("total", &total.group, &total.stats));
+
+            for (scope, name, stats) in scripts.chain(totals) {
+                for (stat, key, value) in stats.rows() {
+                    writer
+                        .write_record([scope, name, stat, &key, &value.to_string()])
+                        .context("Failed to write CSV")?;
+                }
+            }
+            writer.flush().context("Failed to write CSV")?;
+        }
+        StatsFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).context("Failed to write JSON")?
+        ),
+    }
+
+    Ok(())
+}
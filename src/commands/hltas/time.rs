@@ -0,0 +1,305 @@
+use std::{
+    env::current_dir,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use git2::{ErrorCode, Repository};
+use hltas::HLTAS;
+use log::{info, warn};
+
+use crate::{
+    commands::link_hltas::hltases_from_dir,
+    helper,
+    project_toml::{self, ProjectToml},
+    script,
+};
+
+/// A script to time, with the target time of its segment.
+struct Entry {
+    name: String,
+    path: PathBuf,
+    target_time: Option<f64>,
+}
+
+fn format_delta(delta: f64) -> String {
+    format!("{delta:+.3}s")
+}
+
+/// Contents of a file at a git revision, `None` if the file doesn't exist at it.
+///
+/// - A repository without commits has no `HEAD` to compare against yet, which is also `None`.
+/// - Any other revision that doesn't resolve is an error, it is most likely a typo.
+fn file_at_revision(repo: &Repository, revision: &str, path: &Path) -> Result<Option<String>> {
+    let workdir = repo
+        .workdir()
+        .context("Git repository has no working directory")?;
+    let path = path.canonicalize().unwrap_or(path.to_path_buf());
+    let workdir = workdir.canonicalize().unwrap_or(workdir.to_path_buf());
+    let relative = path
+        .strip_prefix(&workdir)
+        .with_context(|| format!("{} is not in the git repository", path.display()))?;
+
+    let tree = match repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_tree())
+    {
+        Ok(tree) => tree,
+        Err(_)
+            if repo
+                .head()
+                .is_err_and(|err| err.code() == ErrorCode::UnbornBranch) =>
+        {
+            return Ok(None)
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to find revision '{revision}'"))
+        }
+    };
+
+    let entry = match tree.get_path(relative) {
+        Ok(entry) => entry,
+        Err(_) => return Ok(None),
+    };
+    let blob = entry
+        .to_object(repo)
+        .and_then(|object| object.peel_to_blob())
+        .context("Failed to read file from git")?;
+
+    Ok(Some(String::from_utf8_lossy(blob.content()).to_string()))
+}
+
+/// Estimated time of a script at a revision, `None` if it didn't exist or didn't parse.
+fn time_at_revision(repo: &Repository, revision: &str, path: &Path) -> Result<Option<f64>> {
+    Ok(file_at_revision(repo, revision, path)?
+        .and_then(|contents| script::parse(&contents).ok())
+        .map(|hltas| script::estimated_time(&hltas)))
+}
+
+fn print_bulks(hltas: &HLTAS) {
+    let mut frame = 0;
+    let mut time = 0.;
+
+    for (i, frame_bulk) in hltas.frame_bulks().enumerate() {
+        let frames = frame_bulk.frame_count.get();
+        let bulk_time = f64::from(frames) * script::frame_time(frame_bulk);
+
+        info!(
+            "  bulk {i} @ frame {frame}: {frames} x {} = {bulk_time:.3}s, at {}",
+            frame_bulk.frame_time,
+            helper::format_time(time + bulk_time)
+        );
+
+        frame += u64::from(frames);
+        time += bulk_time;
+    }
+}
+
+/// The scripts of the project in run order, with the segments if there are any.
+fn project_entries(project_dir: &Path, project: &ProjectToml) -> Result<Vec<Entry>> {
+    if !project.segments.is_empty() {
+        return Ok(project
+            .segments
+            .iter()
+            .map(|segment| Entry {
+                name: segment.name.clone(),
+                path: project_dir.join(&segment.script),
+                target_time: segment.target_time,
+            })
+            .collect());
+    }
+
+    Ok(hltases_from_dir(project_dir)?
+        .into_iter()
+        .map(|path| Entry {
+            name: path
+                .strip_prefix(project_dir)
+                .unwrap_or(&path)
+                .display()
+                .to_string(),
+            path,
+            target_time: None,
+        })
+        .collect())
+}
+
+/// Shows the estimated time of each script and the change since a git revision.
+///
+/// - Times the segments of the current project in order, or all its scripts if it has no segments.
+/// - With `log`, shows the total time at each of the last commits instead.
+pub fn time(
+    files: &[PathBuf],
+    bulks: bool,
+    since: &Option<String>,
+    log: &Option<usize>,
+) -> Result<()> {
+    let current_dir = current_dir().context("Failed to get current directory")?;
+    let project_toml = current_dir.join(project_toml::FILE_NAME);
+    let project = if project_toml.is_file() {
+        Some(ProjectToml::load_from_path(project_toml)?)
+    } else {
+        None
+    };
+
+    let entries = match (files.is_empty(), &project) {
+        (false, _) => files
+            .iter()
+            .map(|path| Entry {
+                name: path.display().to_string(),
+                path: path.clone(),
+                target_time: None,
+            })
+            .collect(),
+        (true, Some(project)) => project_entries(&current_dir, project)?,
+        (true, None) => {
+            bail!("No scripts given\nHelp: Pass the scripts or run from the project directory")
+        }
+    };
+
+    let repo = entries
+        .first()
+        .and_then(|entry| Repository::discover(entry.path.canonicalize().ok()?.parent()?).ok());
+
+    if let Some(log) = log {
+        let Some(repo) = &repo else {
+            bail!("The scripts are not in a git repository");
+        };
+        return time_log(repo, &entries, *log);
+    }
+
+    let since = since.as_deref().unwrap_or("HEAD");
+    if repo.is_none() && since != "HEAD" {
+        bail!("The scripts are not in a git repository");
+    }
+
+    let mut total = 0.;
+    let mut total_since = Some(0.);
+
+    for entry in &entries {
+        let hltas = script::load(&entry.path)?;
+        let time = script::estimated_time(&hltas);
+        total += time;
+
+        let mut line = format!(
+            "{}: {} ({} frames)",
+            entry.name,
+            helper::format_time(time),
+            script::total_frames(&hltas)
+        );
+
+        if let Some(target_time) = entry.target_time {
+            line.push_str(&format!(
+                ", target {} ({})",
+                helper::format_time(target_time),
+                format_delta(time - target_time)
+            ));
+        }
+
+        let time_since = match &repo {
+            Some(repo) => time_at_revision(repo, since, &entry.path)?,
+            None => None,
+        };
+        match time_since {
+            Some(time_since) => {
+                line.push_str(&format!(
+                    ", {} since {since}",
+                    format_delta(time - time_since)
+                ));
+                total_since = total_since.map(|total| total + time_since);
+            }
+            None => {
+                if repo.is_some() {
+                    line.push_str(&format!(", no baseline at {since}"));
+                }
+                total_since = None;
+            }
+        }
+
+        info!("{line}");
+        if bulks {
+            print_bulks(&hltas);
+        }
+    }
+
+    let mut line = format!("total: {}", helper::format_time(total));
+    if let Some(target_time) = project.as_ref().and_then(|project| project.target_time) {
+        line.push_str(&format!(
+            ", target {} ({})",
+            helper::format_time(target_time),
+            format_delta(total - target_time)
+        ));
+    }
+    if let Some(total_since) = total_since.filter(|_| repo.is_some()) {
+        line.push_str(&format!(
+            ", {} since {since}",
+            format_delta(total - total_since)
+        ));
+    }
+    info!("{line}");
+
+    Ok(())
+}
+
+/// Shows the total time at the last commits on the first parent line, with the change each made.
+fn time_log(repo: &Repository, entries: &[Entry], count: usize) -> Result<()> {
+    if repo.head().is_err() {
+        info!("No commits yet");
+        return Ok(());
+    }
+
+    let mut revwalk = repo.revwalk().context("Failed to walk git history")?;
+    revwalk.push_head().context("Failed to find HEAD")?;
+    revwalk
+        .simplify_first_parent()
+        .context("Failed to walk git history")?;
+
+    // one more commit to compare the oldest one against
+    let commits = revwalk
+        .take(count + 1)
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to walk git history")?;
+
+    let mut totals = Vec::new();
+    for oid in &commits {
+        let revision = oid.to_string();
+        let mut total = 0.;
+        let mut missing = 0;
+
+        for entry in entries {
+            match time_at_revision(repo, &revision, &entry.path)? {
+                Some(time) => total += time,
+                None => missing += 1,
+            }
+        }
+
+        totals.push((total, missing));
+    }
+
+    for (i, oid) in commits.iter().take(count).enumerate() {
+        let commit = repo.find_commit(*oid).context("Failed to find commit")?;
+        let summary = commit.summary().unwrap_or_default();
+        let short_id = &oid.to_string()[..7];
+        let (total, missing) = totals[i];
+
+        let mut line = format!("{short_id} {}", helper::format_time(total));
+        if let Some((parent_total, _)) = totals.get(i + 1) {
+            let delta = total - parent_total;
+            let verdict = if delta < 0. {
+                "timesave"
+            } else if delta > 0. {
+                "timeloss"
+            } else {
+                "no change"
+            };
+            line.push_str(&format!(" ({}, {verdict})", format_delta(delta)));
+        }
+        line.push_str(&format!(" {summary}"));
+
+        info!("{line}");
+        if missing > 0 {
+            warn!("  {missing} scripts are missing or don't parse at {short_id}");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::{env::current_dir, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use log::{error, info};
+
+use crate::script;
+
+use super::script_files;
+
+/// Rewrites scripts in older forms to the current format, or only reports them with `check`.
+pub fn upgrade(files: &[PathBuf], check: bool) -> Result<()> {
+    let current_dir = current_dir().context("Failed to get current directory")?;
+    let files = script_files(files)?;
+
+    let mut outdated = 0;
+    let mut failed = 0;
+
+    for file in &files {
+        let display = file.strip_prefix(&current_dir).unwrap_or(file).display();
+        let contents = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+
+        let (upgraded, notes) = match script::upgrade(&contents) {
+            Ok(upgraded) => upgraded,
+            Err(err) => {
+                error!("{display}: {err:#}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        // only outdated fields are rewritten, the layout is checked by 'hltas fmt --check'
+        if notes.is_empty() {
+            continue;
+        }
+
+        outdated += 1;
+        if check {
+            info!("{display} is not upgraded");
+        } else {
+            info!("Upgrading {display}");
+        }
+        for note in &notes {
+            info!("  {display}:{}: {}", note.line, note.message);
+        }
+
+        if !check {
+            // writing in place keeps the hard links to the game directories
+            fs::write(file, upgraded)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} scripts couldn't be upgraded");
+    }
+    if check && outdated > 0 {
+        bail!("{outdated} scripts are not upgraded\nHelp: Run 'hltas upgrade' to upgrade them");
+    }
+
+    Ok(())
+}
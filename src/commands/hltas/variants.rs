@@ -0,0 +1,225 @@
+use std::{fs, num::NonZeroU32, path::Path};
+
+use anyhow::{bail, Context, Result};
+use hltas::types::{AutoMovement, FrameBulk, Line};
+use log::info;
+
+use crate::{
+    cfg::Cfg,
+    cli::VariantField,
+    commands::{hltas::seed, scratch},
+    helper, history, script,
+};
+
+/// More variants than this is most likely a mistake in the range.
+const MAX_VARIANTS: usize = 1000;
+
+/// Directory in the scratch directory the variants of scripts are written to.
+const VARIANTS_DIR: &str = "variants";
+
+/// The values of a range written as `start:end:step`, including `end`.
+fn range_values(range: &str) -> Result<Vec<String>> {
+    let parts = range
+        .split(':')
+        .map(|part| part.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok();
+    let Some([start, end, step]) = parts.as_deref() else {
+        bail!("Invalid range '{range}'\nHelp: Write it as '<start>:<end>:<step>', such as '90:91:0.1'");
+    };
+    let (start, end, step) = (*start, *end, *step);
+    // as many decimals as the start and step are written with, such as `0.3` instead of `0.30000000000000004`
+    let decimals = range
+        .split(':')
+        .map(|part| {
+            script::normalize_number(part)
+                .split_once('.')
+                .map_or(0, |(_, decimals)| decimals.len())
+        })
+        .max()
+        .unwrap_or(0);
+
+    if step <= 0. {
+        bail!("The step of the range has to be positive");
+    }
+
+    let count = ((end - start) / step + 1e-9).floor();
+    if count < 0. {
+        bail!("The range ends before it starts");
+    }
+    if count as usize >= MAX_VARIANTS {
+        bail!("The range has more than {MAX_VARIANTS} values");
+    }
+
+    Ok((0..=count as usize)
+        .map(|i| script::normalize_number(&format!("{:.decimals$}", start + step * i as f64)))
+        .collect())
+}
+
+/// Sets a field of a frame bulk to a value in the text of the frame bulk, keeping its other fields as written.
+fn set_field(
+    text: &str,
+    frame_bulk: &FrameBulk,
+    field: VariantField,
+    value: &str,
+) -> Result<String> {
+    let invalid = || format!("Invalid {field:?} value '{value}'");
+    let mut fields = text
+        .splitn(script::FRAME_BULK_FIELDS.len(), '|')
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    match field {
+        VariantField::Yaw => {
+            value.parse::<f64>().with_context(invalid)?;
+            match frame_bulk.auto_actions.movement {
+                Some(AutoMovement::Strafe(_)) if script::yaw(frame_bulk).is_none() => {
+                    bail!("The frame bulk strafes without a yaw")
+                }
+                None => bail!("The frame bulk doesn't set a yaw"),
+                _ => {}
+            }
+
+            // the yaw comes first, the yaw field of some strafe types has more values after it
+            fields[4] = match fields[4].split_once(' ') {
+                Some((_, rest)) => format!("{value} {rest}"),
+                None => value.to_string(),
+            };
+        }
+        VariantField::Pitch => {
+            value.parse::<f64>().with_context(invalid)?;
+            fields[5] = value.to_string();
+        }
+        VariantField::Frames => {
+            value.parse::<NonZeroU32>().with_context(invalid)?;
+            fields[6] = value.to_string();
+        }
+        VariantField::Frametime => {
+            value.parse::<f64>().with_context(invalid)?;
+            fields[3] = value.to_string();
+        }
+    }
+
+    Ok(fields.join("|"))
+}
+
+/// Writes copies of a script with one frame bulk field or the seed swept over values, and links them.
+///
+/// - The copies are written to `.scratch/variants/<script>/` in the project, replacing the previous ones.
+/// - Prints the `bxt_tas_loadscript` command of each copy.
+pub fn variants<P>(
+    file: P,
+    bulk: Option<usize>,
+    field: Option<VariantField>,
+    range: &Option<String>,
+    values: &[String],
+    seeds: &[u32],
+    random_seeds: Option<usize>,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let file = file.as_ref();
+    let project_dir = history::project_dir_of(file)?;
+    let cfg = Cfg::load(helper::cfg_dir()?)?;
+    let root_dir = helper::exe_dir()?;
+
+    let contents =
+        fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let hltas =
+        script::parse(&contents).with_context(|| format!("Failed to parse {}", file.display()))?;
+    let stem = file
+        .file_stem()
+        .context("Failed to get script name")?
+        .to_string_lossy()
+        .to_string();
+
+    // (name of the variant, contents)
+    let mut variants = Vec::new();
+
+    match (bulk, field) {
+        (Some(bulk), Some(field)) => {
+            let values = match range {
+                Some(range) => range_values(range)?,
+                None => values.to_vec(),
+            };
+            if values.is_empty() {
+                bail!("No values to sweep\nHelp: Use '--range' or '--values'");
+            }
+
+            let Some(line) = hltas
+                .lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| matches!(line, Line::FrameBulk(_)))
+                .map(|(i, _)| i)
+                .nth(bulk)
+            else {
+                bail!(
+                    "The script has no frame bulk {bulk}, it has {}",
+                    hltas.frame_bulks().count()
+                );
+            };
+
+            let Line::FrameBulk(frame_bulk) = &hltas.lines[line] else {
+                unreachable!();
+            };
+            let Some(text) = script::line_texts(&contents).get(line).copied() else {
+                bail!("Failed to find frame bulk {bulk} in {}", file.display());
+            };
+
+            // only the swept field changes, the rest of the script is kept as written
+            let field_name = format!("{field:?}").to_lowercase();
+            for value in values {
+                let text = set_field(text, frame_bulk, field, &value)
+                    .with_context(|| format!("Failed to change frame bulk {bulk}"))?;
+                let variant = script::set_line(&contents, line, &text)?;
+
+                variants.push((format!("{field_name}_{value}"), variant));
+            }
+        }
+        (None, None) => {
+            let non_shared = hltas.properties.seeds.map_or(0, |seeds| seeds.non_shared);
+            let mut all_seeds = seeds
+                .iter()
+                .map(|shared| hltas::types::Seeds {
+                    shared: *shared,
+                    non_shared,
+                })
+                .collect::<Vec<_>>();
+            all_seeds.extend((0..random_seeds.unwrap_or(0)).map(|_| seed::random_seeds()));
+
+            if all_seeds.is_empty() {
+                bail!("Nothing to sweep\nHelp: Use '--bulk' and '--field' with '--range' or '--values', or '--seeds' or '--random-seeds'");
+            }
+
+            for seeds in all_seeds {
+                let seeds = script::seeds_to_string(&seeds);
+                let variant = script::set_property(&contents, "seed", Some(&seeds))?;
+                variants.push((format!("seed_{}", seeds.replace(' ', "_")), variant));
+            }
+        }
+        _ => bail!("'--bulk' and '--field' have to be used together"),
+    }
+
+    if variants.len() > MAX_VARIANTS {
+        bail!("Not writing more than {MAX_VARIANTS} variants");
+    }
+
+    let variants_dir = format!("{VARIANTS_DIR}/{stem}");
+    scratch::remove_scratch_dir(&root_dir, &cfg, &project_dir, &variants_dir)?;
+
+    info!("Writing {} variants of {}", variants.len(), file.display());
+    for (name, contents) in variants {
+        let scratch = scratch::write_scratch(
+            &project_dir,
+            &format!("{variants_dir}/{stem}_{name}.hltas"),
+            &contents,
+        )?;
+        let link = scratch::link_scratch(&root_dir, &cfg, &project_dir, scratch)?;
+
+        info!("{name}: bxt_tas_loadscript {link}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use git2::{ConfigLevel, Repository};
+use log::info;
+
+use crate::{files, helper};
+
+use super::project;
+
+const DIFF_DRIVER_COMMAND: &str = "diff.hltas.command";
+const DIFF_DRIVER_TEXTCONV: &str = "diff.hltas.textconv";
+
+/// Rewrites the git hooks of the current project, or all projects if run from the root dir.
+pub fn update_hooks() -> Result<()> {
+    for project_dir in helper::current_project_dirs()? {
+        let repo = match Repository::open(&project_dir) {
+            Ok(repo) => repo,
+            Err(_) => {
+                info!("{} has no git repository, skipping", project_dir.display());
+                continue;
+            }
+        };
+
+        info!("Updating hooks of {}", project_dir.display());
+        files::write_git_hooks(repo.path().join("hooks"))?;
+        register_diff_driver(&repo)?;
+        project::write_gitattributes(&project_dir)?;
+        project::write_gitignore(&project_dir)?;
+    }
+
+    Ok(())
+}
+
+/// Sets 'diff-hltas' as the `hltas` diff driver in the repository config.
+///
+/// - '.gitattributes' assigns the driver to the scripts.
+/// - The textconv is used where git doesn't run the driver, like `git log -p`.
+pub fn register_diff_driver(repo: &Repository) -> Result<()> {
+    let command = format!("\"{}\" diff-hltas", helper::git_exe_path()?);
+    let textconv = format!("{command} --textconv");
+
+    let mut config = repo
+        .config()
+        .and_then(|config| config.open_level(ConfigLevel::Local))
+        .context("Failed to open git config")?;
+
+    let is_registered = |config: &git2::Config, key, value: &str| {
+        config.get_string(key).ok().as_deref() == Some(value)
+    };
+    if is_registered(&config, DIFF_DRIVER_COMMAND, &command)
+        && is_registered(&config, DIFF_DRIVER_TEXTCONV, &textconv)
+    {
+        return Ok(());
+    }
+
+    info!("Registering hltas diff driver");
+    config
+        .set_str(DIFF_DRIVER_COMMAND, &command)
+        .and_then(|_| config.set_str(DIFF_DRIVER_TEXTCONV, &textconv))
+        .context("Failed to set hltas diff driver")
+}
@@ -1,18 +1,24 @@
 use std::{
+    collections::HashMap,
     env::current_dir,
+    fs,
+    io::{self, IsTerminal},
     path::{Path, PathBuf},
     time::Duration,
 };
 
-use anyhow::{Context, Result};
-use log::{debug, info};
+use anyhow::{bail, Context, Result};
+use log::{debug, error, info, warn};
 
 use crate::{
-    cfg::{self, Cfg},
-    helper, project_toml,
+    cfg::{self, Cfg, DivergedScripts},
+    helper, history,
+    link_registry::{self, LinkRegistry},
+    lint::{self, Severity},
+    project_toml::{self, ProjectToml, Scripts},
 };
 
-pub fn link_hltas(keep_alive: bool) -> Result<()> {
+pub fn link_hltas(keep_alive: bool, lint: bool) -> Result<()> {
     let current_dir = current_dir().context("Failed to get current directory")?;
     let project_toml_path = current_dir.join(project_toml::FILE_NAME);
 
@@ -31,43 +37,91 @@ pub fn link_hltas(keep_alive: bool) -> Result<()> {
     let cfg = Cfg::load(cfg_path).context("Failed to load cfg")?;
 
     if keep_alive {
+        let project_dir = project_toml_path.is_file().then_some(current_dir.as_path());
+        let mut modified_times = HashMap::new();
+
         loop {
-            link_hltas_once(project_toml_path.is_file(), &current_dir, &cfg, true)?;
+            history::snapshot_changed(project_dir, root_dir, &cfg, &mut modified_times);
+            link_hltas_once(
+                project_toml_path.is_file(),
+                &current_dir,
+                &cfg,
+                true,
+                false,
+                lint,
+            )?;
             std::thread::sleep(Duration::from_secs(1));
         }
     } else {
-        link_hltas_once(project_toml_path.is_file(), current_dir, &cfg, false)?;
+        link_hltas_once(
+            project_toml_path.is_file(),
+            current_dir,
+            &cfg,
+            false,
+            true,
+            lint,
+        )?;
     }
 
     Ok(())
 }
 
+/// Finds the scripts of a project, using the script globs of its project config.
+pub fn hltases_from_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    let project_toml_path = dir.join(project_toml::FILE_NAME);
+    let scripts = if project_toml_path.is_file() {
+        ProjectToml::load_from_path(project_toml_path)?.scripts
+    } else {
+        Scripts::default()
+    };
+
+    scripts.find(dir)
+}
+
+/// Directory in the game directories that namespaced scripts are linked into.
+pub const NAMESPACE_DIR: &str = "tas";
+
+/// Returns where a script is linked to relative to the game directory.
+///
+/// - This is also the name `bxt_tas_loadscript` takes.
+pub fn script_link_path<P, P2>(cfg: &Cfg, project_dir: P, hltas: P2) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let project_dir = project_dir.as_ref();
+    let hltas = hltas.as_ref();
+    let relative = hltas.strip_prefix(project_dir).with_context(|| {
+        format!(
+            "{} is not in the project {}",
+            hltas.display(),
+            project_dir.display()
+        )
+    })?;
+
+    if cfg.namespace_scripts {
+        let project_name = project_dir
+            .file_name()
+            .context("Failed to get project name")?;
+
+        Ok(Path::new(NAMESPACE_DIR).join(project_name).join(relative))
+    } else {
+        Ok(relative.to_path_buf())
+    }
+}
+
+/// Links the scripts of the current project, or all projects if run from the root dir.
+///
+/// - With `lint`, scripts that fail to parse aren't linked and their existing links are kept.
+/// - Without `interactive`, `diverged_scripts = "ask"` acts as `Stop` since nobody can answer.
 pub fn link_hltas_once<P: AsRef<Path>>(
     is_in_project_dir: bool,
     current_dir: P,
     cfg: &Cfg,
     silent: bool,
+    interactive: bool,
+    lint: bool,
 ) -> Result<()> {
-    let hltases_from_dir = |dir: &Path| -> Result<Vec<PathBuf>> {
-        let mut hltases = Vec::new();
-
-        for dir in dir
-            .read_dir()
-            .with_context(|| format!("Failed to read directory {}", dir.display()))?
-        {
-            let dir = dir.context("Failed to read dir")?;
-            let path = dir.path();
-
-            if let Some(extension) = path.extension() {
-                if extension == "hltas" {
-                    hltases.push(path);
-                }
-            }
-        }
-
-        Ok(hltases)
-    };
-
     let current_dir = current_dir.as_ref();
     let root_dir = if is_in_project_dir {
         let tas_dir = current_dir.parent().context("Failed to get root dir")?;
@@ -77,8 +131,12 @@ pub fn link_hltas_once<P: AsRef<Path>>(
     };
     let half_life_dir = root_dir.join(&cfg.half_life_dir);
 
+    // project dir and hltas
     let hltases = if is_in_project_dir {
         hltases_from_dir(current_dir)?
+            .into_iter()
+            .map(|hltas| (current_dir.to_path_buf(), hltas))
+            .collect::<Vec<_>>()
     } else {
         let projects = current_dir.join(&cfg.project_dir);
 
@@ -88,7 +146,15 @@ pub fn link_hltas_once<P: AsRef<Path>>(
             let project = project.context("Failed to read project file")?;
             let path = project.path();
 
-            hltases.extend(hltases_from_dir(&path)?);
+            if !path.is_dir() {
+                continue;
+            }
+
+            hltases.extend(
+                hltases_from_dir(&path)?
+                    .into_iter()
+                    .map(|hltas| (path.clone(), hltas)),
+            );
         }
 
         hltases
@@ -96,31 +162,381 @@ pub fn link_hltas_once<P: AsRef<Path>>(
 
     debug!("HLTASes: {:?}", hltases);
 
-    for hltas in hltases {
-        // hard-link to main game
+    let registry_path = root_dir.join(link_registry::FILE_NAME);
+    let mut registry = LinkRegistry::load_from_path(&registry_path)?;
+    let registry_before = registry.links.clone();
+    let relative_to_root = |path: &Path| path.strip_prefix(root_dir).unwrap_or(path).to_path_buf();
+
+    let scope = if is_in_project_dir {
+        relative_to_root(current_dir)
+    } else {
+        cfg.project_dir.clone()
+    };
+
+    let mut game_dirs = vec![half_life_dir];
+    if let Some(second_game_dir) = &cfg.no_client_dll_dir {
+        game_dirs.push(root_dir.join(second_game_dir));
+    }
+
+    // hltas and its link path relative to the game dir
+    let mut planned = Vec::new();
+    // broken scripts that are left as they were linked last time
+    let mut skipped = Vec::new();
+    for (project_dir, hltas) in hltases {
+        if lint && !lint_before_link(&hltas, silent)? {
+            skipped.push(relative_to_root(&hltas));
+            continue;
+        }
+
+        let link = script_link_path(cfg, &project_dir, &hltas)?;
+        planned.push((project_dir, hltas, link));
+    }
+
+    // the same link from different scripts would silently overwrite each other
+    let mut collisions = Vec::new();
+    for (i, (_, hltas, link)) in planned.iter().enumerate() {
+        for (_, other, _) in planned[..i].iter().filter(|(_, _, other)| other == link) {
+            collisions.push(format!(
+                "{} and {} both link to {}",
+                other.display(),
+                hltas.display(),
+                link.display()
+            ));
+        }
+
+        // links owned by other projects that we are not going through
+        let game_dir_hltas = relative_to_root(&game_dirs[0].join(link));
+        for owned in registry.links.iter().filter(|l| {
+            l.link == game_dir_hltas
+                && !l.source.starts_with(&scope)
+                && root_dir.join(&l.source).is_file()
+        }) {
+            collisions.push(format!(
+                "{} and {} both link to {}",
+                owned.source.display(),
+                hltas.display(),
+                link.display()
+            ));
+        }
+    }
+
+    if !collisions.is_empty() {
+        bail!(
+            "Scripts link to the same file in the game directory:\n{}\nHelp: Rename the scripts or set 'namespace_scripts = true' in the config",
+            collisions.join("\n")
+        );
+    }
+
+    let mut linked = Vec::new();
+
+    for (project_dir, hltas, link) in planned {
         if !silent {
             info!("Linking {}", hltas.display());
         }
-        let game_dir_hltas = half_life_dir.join(hltas.file_name().unwrap());
-        debug!(
-            "Linking {} to {}",
-            hltas.display(),
-            game_dir_hltas.display()
-        );
-        helper::force_link(&hltas, &game_dir_hltas).context("Failed to hard link hltas")?;
 
-        if let Some(second_game_dir) = &cfg.no_client_dll_dir {
-            // hard-link to second game
-            let game_dir_hltas = root_dir.join(second_game_dir.join(hltas.file_name().unwrap()));
+        let game_dir_hltases = game_dirs
+            .iter()
+            .map(|game_dir| game_dir.join(&link))
+            .collect::<Vec<_>>();
+        pull_diverged(&project_dir, &hltas, &game_dir_hltases, cfg, interactive)?;
+        // recorded so later only files that are still our links get removed
+        let file_id = helper::file_id(&hltas)?;
+
+        // hard-link to main game and second game
+        for game_dir in &game_dirs {
+            let game_dir_hltas = game_dir.join(&link);
 
             debug!(
                 "Linking {} to {}",
                 hltas.display(),
                 game_dir_hltas.display()
             );
+            if let Some(parent) = game_dir_hltas.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
             helper::force_link(&hltas, &game_dir_hltas).context("Failed to hard link hltas")?;
+            linked.push((
+                relative_to_root(&hltas),
+                relative_to_root(&game_dir_hltas),
+                file_id.clone(),
+            ));
         }
     }
 
+    // remove links we own that weren't linked this time, but only from the projects we went through
+    for link in registry.links.clone() {
+        if !link.source.starts_with(&scope)
+            || skipped.contains(&link.source)
+            || linked.iter().any(|(source, game_dir_hltas, _)| {
+                *source == link.source && *game_dir_hltas == link.link
+            })
+        {
+            continue;
+        }
+
+        let game_dir_hltas = root_dir.join(&link.link);
+        if game_dir_hltas.is_file() {
+            if link.is_owned(root_dir)? {
+                info!("Removing stale link {}", game_dir_hltas.display());
+                fs::remove_file(&game_dir_hltas).with_context(|| {
+                    format!("Failed to remove stale link {}", game_dir_hltas.display())
+                })?;
+            } else {
+                warn!(
+                    "{} was replaced since it was linked, leaving it and forgetting the link",
+                    game_dir_hltas.display()
+                );
+            }
+        }
+
+        registry.links.retain(|l| *l != link);
+    }
+
+    for (source, game_dir_hltas, file_id) in linked {
+        registry.insert(source, game_dir_hltas, file_id);
+    }
+
+    if registry.links != registry_before {
+        registry.save_to_path(&registry_path)?;
+    }
+
     Ok(())
 }
+
+/// If a game dir copy of a script is no longer the same file and has edits the script doesn't have.
+///
+/// - Hard linked files share their contents and modified time, so different contents means the link broke.
+/// - Only a copy modified after the script is diverged, an older one was left behind by the script being replaced.
+fn is_diverged(hltas: &Path, game_dir_hltas: &Path) -> Result<bool> {
+    let Ok(game_dir_metadata) = fs::metadata(game_dir_hltas) else {
+        return Ok(false);
+    };
+    let metadata =
+        fs::metadata(hltas).with_context(|| format!("Failed to read {}", hltas.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if metadata.dev() == game_dir_metadata.dev() && metadata.ino() == game_dir_metadata.ino() {
+            return Ok(false);
+        }
+    }
+
+    let modified = metadata.modified().context("Failed to get modified time")?;
+    let game_dir_modified = game_dir_metadata
+        .modified()
+        .context("Failed to get modified time")?;
+    if game_dir_modified <= modified {
+        return Ok(false);
+    }
+
+    let contents =
+        fs::read(hltas).with_context(|| format!("Failed to read {}", hltas.display()))?;
+    let game_dir_contents = fs::read(game_dir_hltas)
+        .with_context(|| format!("Failed to read {}", game_dir_hltas.display()))?;
+
+    Ok(contents != game_dir_contents)
+}
+
+/// Copies the diverged game dir copy of a script back into the project before it is relinked.
+///
+/// - Relinking replaces the copies with the script, which would lose the edits.
+/// - Copies in more than one game dir with different edits stop linking, picking one would lose the others.
+/// - The script is snapshotted first when snapshots are on.
+/// - `diverged_scripts` in the config can make this ask first or stop instead.
+fn pull_diverged(
+    project_dir: &Path,
+    hltas: &Path,
+    game_dir_hltases: &[PathBuf],
+    cfg: &Cfg,
+    interactive: bool,
+) -> Result<()> {
+    // diverged copies with different contents
+    let mut diverged: Vec<(&PathBuf, Vec<u8>)> = Vec::new();
+    for game_dir_hltas in game_dir_hltases {
+        if is_diverged(hltas, game_dir_hltas)? {
+            let contents = fs::read(game_dir_hltas)
+                .with_context(|| format!("Failed to read {}", game_dir_hltas.display()))?;
+            if diverged.iter().all(|(_, other)| *other != contents) {
+                diverged.push((game_dir_hltas, contents));
+            }
+        }
+    }
+
+    let edited = match diverged.as_slice() {
+        [] => return Ok(()),
+        [(edited, _)] => *edited,
+        _ => bail!(
+            "Stopped linking, {} were edited differently after their links to {} broke\nHelp: Merge the edits into {} and remove the copies",
+            diverged
+                .iter()
+                .map(|(path, _)| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" and "),
+            hltas.display(),
+            hltas.display()
+        ),
+    };
+
+    warn!(
+        "{} was edited after its link to {} broke",
+        edited.display(),
+        hltas.display()
+    );
+
+    // git hooks and the keep alive loops can't answer, taking an empty answer as no would lose the edits
+    let diverged_scripts = match cfg.diverged_scripts {
+        DivergedScripts::Ask if !interactive || !io::stdin().is_terminal() => DivergedScripts::Stop,
+        diverged_scripts => diverged_scripts,
+    };
+
+    match diverged_scripts {
+        DivergedScripts::Pull => {}
+        DivergedScripts::Ask => {
+            if !helper::confirm(
+                "Copy it back into the project? Otherwise it is replaced by the project version",
+            )? {
+                return Ok(());
+            }
+        }
+        DivergedScripts::Stop => bail!(
+            "Stopped linking so the edits in {} aren't lost\nHelp: Copy it over {} or remove it, or set 'diverged_scripts' in the config",
+            edited.display(),
+            hltas.display()
+        ),
+    }
+
+    if cfg.history_limit > 0 {
+        if let Some(snapshot) = history::snapshot(project_dir, hltas)? {
+            info!("Saved snapshot {}", snapshot.display());
+        }
+    }
+
+    info!("Copying {} back to {}", edited.display(), hltas.display());
+    fs::copy(edited, hltas)
+        .with_context(|| format!("Failed to copy {} to {}", edited.display(), hltas.display()))?;
+
+    Ok(())
+}
+
+/// Returns if the script parses, reporting the errors if it doesn't.
+fn lint_before_link(hltas: &Path, silent: bool) -> Result<bool> {
+    let contents =
+        fs::read_to_string(hltas).with_context(|| format!("Failed to read {}", hltas.display()))?;
+    let diagnostics = lint::lint(&contents);
+
+    if !lint::has_errors(&diagnostics) {
+        return Ok(true);
+    }
+
+    if !silent {
+        for diagnostic in diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            error!("{}:{diagnostic}", hltas.display());
+        }
+        warn!("Not linking {}, it has errors", hltas.display());
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "version 1\nframes\n----------|------|------|0.001|-|-|1\n";
+
+    /// A root dir in the temp dir with a project and the game dirs of the default config.
+    fn temp_root_dir(name: &str) -> PathBuf {
+        let root_dir =
+            std::env::temp_dir().join(format!("hltas_manager_{name}_{}", helper::random_u64()));
+        for dir in ["tas/p", "Half-Life", "NO_CLIENT_DLL"] {
+            fs::create_dir_all(root_dir.join(dir)).unwrap();
+        }
+
+        root_dir
+    }
+
+    fn test_cfg() -> Cfg {
+        Cfg {
+            history_limit: 0,
+            ..Cfg::default()
+        }
+    }
+
+    #[test]
+    fn removes_link_edited_in_the_game_after_its_script_is_renamed() {
+        let root_dir = temp_root_dir("renamed");
+        let cfg = test_cfg();
+        let script = root_dir.join("tas/p/a.hltas");
+        fs::write(&script, SCRIPT).unwrap();
+        link_hltas_once(false, &root_dir, &cfg, true, false, false).unwrap();
+
+        // edited through the hard link, then renamed before linking again
+        let link = root_dir.join("Half-Life/a.hltas");
+        fs::write(&link, format!("{SCRIPT}// edited\n")).unwrap();
+        fs::rename(&script, root_dir.join("tas/p/b.hltas")).unwrap();
+        link_hltas_once(false, &root_dir, &cfg, true, false, false).unwrap();
+
+        assert!(!link.exists());
+        assert!(!root_dir.join("NO_CLIENT_DLL/a.hltas").exists());
+        assert_eq!(
+            fs::read_to_string(root_dir.join("Half-Life/b.hltas")).unwrap(),
+            format!("{SCRIPT}// edited\n")
+        );
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn stops_on_copies_edited_differently_in_both_game_dirs() {
+        let root_dir = temp_root_dir("diverged");
+        let cfg = test_cfg();
+        let script = root_dir.join("tas/p/a.hltas");
+        fs::write(&script, SCRIPT).unwrap();
+        link_hltas_once(false, &root_dir, &cfg, true, false, false).unwrap();
+
+        // the links broke and each copy was edited after the script
+        for (game_dir, edit) in [("Half-Life", "// a\n"), ("NO_CLIENT_DLL", "// b\n")] {
+            let link = root_dir.join(game_dir).join("a.hltas");
+            fs::remove_file(&link).unwrap();
+            fs::write(&link, format!("{SCRIPT}{edit}")).unwrap();
+        }
+        fs::File::options()
+            .write(true)
+            .open(&script)
+            .unwrap()
+            .set_modified(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap();
+
+        assert!(link_hltas_once(false, &root_dir, &cfg, true, false, false).is_err());
+        assert_eq!(fs::read_to_string(&script).unwrap(), SCRIPT);
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_file_that_replaced_a_link() {
+        let root_dir = temp_root_dir("replaced");
+        let cfg = test_cfg();
+        let script = root_dir.join("tas/p/a.hltas");
+        fs::write(&script, SCRIPT).unwrap();
+        link_hltas_once(false, &root_dir, &cfg, true, false, false).unwrap();
+
+        // a different file put where the link was
+        let link = root_dir.join("Half-Life/a.hltas");
+        fs::remove_file(&link).unwrap();
+        fs::write(&link, SCRIPT).unwrap();
+        fs::remove_file(&script).unwrap();
+        link_hltas_once(false, &root_dir, &cfg, true, false, false).unwrap();
+
+        assert!(link.is_file());
+        assert!(!root_dir.join("NO_CLIENT_DLL/a.hltas").exists());
+
+        fs::remove_dir_all(root_dir).unwrap();
+    }
+}
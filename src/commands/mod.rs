@@ -1,17 +1,25 @@
+pub mod bundle;
+pub mod diff_hltas;
 pub mod games;
+pub mod hltas;
+pub mod hooks;
 pub mod install;
 pub mod link_hltas;
 pub mod project;
+pub mod projects;
 pub mod run_game;
+pub mod scratch;
+pub mod segments;
 pub mod sync;
 pub mod sync_saves;
+pub mod userconfig;
 
 use anyhow::Result;
 use log::info;
 
 use crate::{
     cfg::Cfg,
-    cli::{Cli, Commands},
+    cli::{Cli, Commands, HltasCommands, ProjectCommands, SeedCommands},
     commands::run_game::RunGameFlags,
     helper::{self},
 };
@@ -47,8 +55,9 @@ pub fn run(cli: Cli) -> Result<()> {
             game_name,
             init_git,
             no_init_git,
+            git_remote,
         } => {
-            new(project_name, game_name, *init_git, *no_init_git)?;
+            new(project_name, game_name, *init_git, *no_init_git, git_remote)?;
             info!("Created project!");
         }
         Commands::Init {
@@ -56,10 +65,39 @@ pub fn run(cli: Cli) -> Result<()> {
             game_name,
             init_git,
             no_init_git,
+            git_remote,
         } => {
-            init(folder_name, game_name, *init_git, *no_init_git)?;
+            init(folder_name, game_name, *init_git, *no_init_git, git_remote)?;
             info!("Initialized project!");
         }
+        Commands::Project { command } => match command {
+            ProjectCommands::Export {
+                project_name,
+                output,
+            } => {
+                bundle::export(project_name, output)?;
+                info!("Exported project!");
+            }
+            ProjectCommands::Import {
+                bundle,
+                project_name,
+                overwrite,
+            } => {
+                bundle::import(bundle, project_name, *overwrite)?;
+                info!("Imported project!");
+            }
+            ProjectCommands::CheckSegments { project_name } => {
+                segments::check_segments(project_name)?;
+                info!("Segments are ready!");
+            }
+            ProjectCommands::SetVerifiedBuild { project_name } => {
+                projects::set_verified_build(project_name)?;
+                info!("Recorded verified build!");
+            }
+        },
+        Commands::Projects => {
+            projects::projects()?;
+        }
         Commands::Games => {
             // load config
             let cfg = helper::cfg_dir()?;
@@ -79,6 +117,8 @@ pub fn run(cli: Cli) -> Result<()> {
             height,
             no_bxt,
             script: run_script,
+            segment,
+            seed,
             params,
             r_input,
             game_override,
@@ -98,6 +138,8 @@ pub fn run(cli: Cli) -> Result<()> {
                 },
                 RunGameBxtFlags {
                     run_script,
+                    segment,
+                    seed,
                     optim_games,
                     sim: *sim,
                     record: *record,
@@ -105,8 +147,8 @@ pub fn run(cli: Cli) -> Result<()> {
                 },
             )?;
         }
-        Commands::LinkHLTAS { keep_alive } => {
-            link_hltas(*keep_alive)?;
+        Commands::LinkHLTAS { keep_alive, lint } => {
+            link_hltas(*keep_alive, *lint)?;
             info!("Linked hltases!");
         }
         Commands::SyncSaves { keep_alive } => {
@@ -115,6 +157,120 @@ pub fn run(cli: Cli) -> Result<()> {
         Commands::Sync => {
             sync()?;
         }
+        Commands::UpdateHooks => {
+            hooks::update_hooks()?;
+            info!("Updated hooks!");
+        }
+        Commands::Hltas { command } => match command {
+            HltasCommands::Info { file } => {
+                hltas::info::info(file)?;
+            }
+            HltasCommands::Lint { files } => {
+                hltas::lint::lint(files)?;
+            }
+            HltasCommands::Fmt { files, check } => {
+                hltas::fmt::fmt(files, *check)?;
+            }
+            HltasCommands::Join { files, output } => {
+                hltas::join::join(files, output)?;
+                info!("Joined scripts!");
+            }
+            HltasCommands::Split {
+                file,
+                at,
+                save_prefix,
+            } => {
+                hltas::split::split(file, at, save_prefix)?;
+                info!("Split script!");
+            }
+            HltasCommands::Grep { files, query, json } => {
+                hltas::grep::grep(files, query, *json)?;
+            }
+            HltasCommands::Stats { files, format } => {
+                hltas::stats::stats(files, *format)?;
+            }
+            HltasCommands::Export {
+                file,
+                format,
+                output,
+            } => {
+                hltas::convert::export(file, *format, output)?;
+            }
+            HltasCommands::Import {
+                input,
+                format,
+                output,
+            } => {
+                hltas::convert::import(input, *format, output)?;
+                info!("Imported script!");
+            }
+            HltasCommands::Variants {
+                file,
+                bulk,
+                field,
+                range,
+                values,
+                seeds,
+                random_seeds,
+            } => {
+                hltas::variants::variants(
+                    file,
+                    *bulk,
+                    *field,
+                    range,
+                    values,
+                    seeds,
+                    *random_seeds,
+                )?;
+            }
+            HltasCommands::Upgrade { files, check } => {
+                hltas::upgrade::upgrade(files, *check)?;
+            }
+            HltasCommands::History { file } => {
+                hltas::history::history(file)?;
+            }
+            HltasCommands::Restore { file, snapshot } => {
+                hltas::history::restore(file, snapshot)?;
+                info!("Restored script!");
+            }
+            HltasCommands::Saves { dot } => {
+                hltas::saves::saves(*dot)?;
+            }
+            HltasCommands::Seed { command } => match command {
+                SeedCommands::Show { files } => hltas::seed::show(files)?,
+                SeedCommands::Set {
+                    files,
+                    shared,
+                    non_shared,
+                    all,
+                } => {
+                    hltas::seed::set(files, *all, *shared, *non_shared)?;
+                    info!("Set seeds!");
+                }
+                SeedCommands::Randomize { files, all } => {
+                    hltas::seed::randomize(files, *all)?;
+                    info!("Randomized seeds!");
+                }
+            },
+            HltasCommands::Time {
+                files,
+                bulks,
+                since,
+                log,
+            } => {
+                hltas::time::time(files, *bulks, since, log)?;
+            }
+        },
+        Commands::DiffHltas { args, textconv } => {
+            if *textconv {
+                diff_hltas::textconv(args)?;
+            } else {
+                diff_hltas::diff_hltas(args)?;
+            }
+        }
+        Commands::Userconfig { action, game } => {
+            userconfig::userconfig(*action, game)?;
+        }
     }
 
     Ok(())
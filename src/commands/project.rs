@@ -1,31 +1,28 @@
 use std::{
-    fs::{self, File, OpenOptions},
-    io::Write,
+    fs,
     path::{Path, PathBuf},
-    process,
 };
 
 use anyhow::{bail, Context, Result};
 use fs_extra::dir::CopyOptions;
-use log::info;
-use sha2::{Digest, Sha256};
+use git2::{IndexAddOption, Repository, Signature};
+use log::{info, warn};
 
 use crate::{
     cfg::Cfg,
-    files, helper,
+    files, helper, managed_block,
     project_toml::{self, ProjectToml},
-    DEFAULT_GAME,
+    DEFAULT_GAME, NAME,
 };
 
-use super::games;
-
-use lazy_static::lazy_static;
+use super::{games, hooks, userconfig};
 
 pub fn new(
     project_name: &str,
     game_name: &Option<String>,
     init_git: bool,
     no_init_git: bool,
+    git_remote: &Option<PathBuf>,
 ) -> Result<()> {
     let cfg = helper::cfg_dir()?;
     let cfg = Cfg::load(cfg)?;
@@ -39,7 +36,7 @@ pub fn new(
         fs::create_dir(&project_dir).context("Failed to create project folder")?;
     }
 
-    init_project(project_dir, game_name, init_git, no_init_git)
+    init_project(project_dir, game_name, init_git, no_init_git, git_remote)
 }
 
 pub fn init(
@@ -47,6 +44,7 @@ pub fn init(
     game_name: &Option<String>,
     init_git: bool,
     no_init_git: bool,
+    git_remote: &Option<PathBuf>,
 ) -> Result<()> {
     let cfg = helper::cfg_dir()?;
     let cfg = Cfg::load(cfg)?;
@@ -58,7 +56,7 @@ pub fn init(
         bail!("Project folder does not exist, Help: Use 'new' to create a new project.");
     }
 
-    init_project(project_dir, game_name, init_git, no_init_git)
+    init_project(project_dir, game_name, init_git, no_init_git, git_remote)
 }
 
 fn init_project<P>(
@@ -66,6 +64,7 @@ fn init_project<P>(
     game_name: &Option<String>,
     init_git: bool,
     no_init_git: bool,
+    git_remote: &Option<PathBuf>,
 ) -> Result<()>
 where
     P: AsRef<Path>,
@@ -96,6 +95,7 @@ where
 
         let project = ProjectToml {
             game: game_name_full.to_string(),
+            ..Default::default()
         };
 
         project.save_to_path(project_toml)?;
@@ -111,7 +111,7 @@ where
     override_userconfig(&game_dir, &second_game_dir)?;
 
     let init_git = {
-        if init_git {
+        if init_git || git_remote.is_some() {
             true
         } else if no_init_git {
             false
@@ -121,7 +121,7 @@ where
     };
 
     if init_git {
-        set_up_git(&project_dir, &cfg)?;
+        set_up_git(&project_dir, git_remote)?;
     }
 
     Ok(())
@@ -132,38 +132,12 @@ where
     P: AsRef<Path>,
     P2: AsRef<Path>,
 {
-    let config = "exec hltas.cfg\nloadtas2";
-
-    let userconfig_path = "userconfig.cfg";
-    let game_dir = game_dir.as_ref();
-
     info!("Writing userconfig.cfg for game directory...");
-    let game_dir_userconfig = game_dir.join(userconfig_path);
-    if game_dir_userconfig.is_file() {
-        OpenOptions::new()
-            .append(true)
-            .open(game_dir_userconfig)?
-            .write_all(format!("\n{config}").as_bytes())
-            .context("Failed to write to userconfig.cfg")?;
-    } else {
-        fs::write(game_dir_userconfig, config).context("Failed to write to userconfig.cfg")?;
-    }
+    userconfig::enable(game_dir)?;
 
     if let Some(second_game_dir) = second_game_dir {
-        let second_game_dir = second_game_dir.as_ref();
-        let second_game_dir_userconfig = second_game_dir.join(userconfig_path);
-
         info!("Writing userconfig.cfg for second game directory...");
-        if second_game_dir_userconfig.is_file() {
-            OpenOptions::new()
-                .append(true)
-                .open(second_game_dir_userconfig)?
-                .write_all(format!("\n{config}").as_bytes())
-                .context("Failed to write to userconfig.cfg")?;
-        } else {
-            fs::write(second_game_dir_userconfig, config)
-                .context("Failed to write to userconfig.cfg")?;
-        }
+        userconfig::enable(second_game_dir)?;
     }
 
     Ok(())
@@ -262,63 +236,169 @@ fn game_dir_validate(cfg: &Cfg, game_name: &str) -> Result<()> {
 }
 
 const GIT_IGNORE: &str = include_str!("../../resource/git_ignores/project");
-lazy_static! {
-    static ref GIT_IGNORE_SHA_256: Vec<u8> = {
-        let mut hasher = Sha256::new();
-        hasher.update(GIT_IGNORE);
-        hasher.finalize().to_vec()
-    };
-}
 
-fn set_up_git<P>(project_dir: P, cfg: &Cfg) -> Result<()>
+const GIT_ATTRIBUTES: &str = "*.hltas text diff=hltas\n";
+
+fn set_up_git<P>(project_dir: P, git_remote: &Option<PathBuf>) -> Result<()>
 where
     P: AsRef<Path>,
 {
     let project_dir = project_dir.as_ref();
 
-    if project_dir.join(".git").is_dir() {
+    let repo = if project_dir.join(".git").is_dir() {
         info!("Project already has a git repository, skipping git init");
+        Repository::open(project_dir).context("Failed to open git repository")?
     } else {
         info!("Setting up git repository...");
-        process::Command::new("git")
-        .current_dir(project_dir)
-        .arg("init")
-        .output()
-        .context("Failed to init git\nHelp: Use '--no-init-git' to skip git init\nNote: This process failing could be due to git not being installed")?;
-    }
+        Repository::init(project_dir)
+            .context("Failed to init git\nHelp: Use '--no-init-git' to skip git init")?
+    };
 
-    // add hardlink hook to .git/hooks/post-checkout
-    let post_checkout_hook_path = project_dir.join(".git/hooks/post-checkout");
+    // add hooks that link the scripts after checkouts, merges and rebases
+    files::write_git_hooks(repo.path().join("hooks"))?;
+    hooks::register_diff_driver(&repo)?;
 
-    files::write_hard_link_shell_hook(post_checkout_hook_path, cfg)?;
+    write_gitignore(project_dir)?;
+    write_gitattributes(project_dir)?;
 
-    // create .gitignore file
-    let gitignore_path = project_dir.join(".gitignore");
+    if let Some(git_remote) = git_remote {
+        set_up_git_remote(&repo, git_remote)?;
+    }
 
-    if gitignore_path.is_file() {
-        // append to .gitignore if sha256 isn't the same
-        if helper::sha_256_file(&gitignore_path).context("Failed to get sha256 of .gitignore")?
-            != *GIT_IGNORE_SHA_256
-        {
-            let mut file = fs::OpenOptions::new()
-                .append(true)
-                .open(&gitignore_path)
-                .context("Failed to open .gitignore")?;
+    initial_commit(&repo)
+}
 
-            file.write_all(GIT_IGNORE.as_bytes())
-                .context("Failed to write to .gitignore")?;
+/// The ignore list that older versions appended to '.gitignore' without a managed block.
+const LEGACY_GIT_IGNORE: [&str; 5] = [
+    "*.bat",
+    "*.ps1",
+    "debug.log",
+    "steam_appid.txt",
+    "htmlcache/",
+];
+
+/// Adds or updates the managed block in the '.gitignore' of the project.
+///
+/// - Copies of the ignore list that older versions appended are replaced by the block.
+pub(super) fn write_gitignore<P>(project_dir: P) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let gitignore_path = project_dir.as_ref().join(".gitignore");
+    let gitignore = if gitignore_path.is_file() {
+        fs::read_to_string(&gitignore_path).context("Failed to read .gitignore")?
+    } else {
+        String::new()
+    };
 
-            let gitignore = format!("\n{GIT_IGNORE}");
+    let without_legacy = managed_block::edit_outside(&gitignore, "#", |text| {
+        managed_block::strip_runs(text, &LEGACY_GIT_IGNORE).0
+    });
+    let updated_gitignore = managed_block::upsert(&without_legacy, "#", GIT_IGNORE);
 
-            file.write_all(gitignore.as_bytes())
-                .context("Failed to write to .gitignore")?;
-        }
+    if updated_gitignore != gitignore {
+        info!("Writing .gitignore file...");
+        fs::write(&gitignore_path, updated_gitignore).context("Failed to write to .gitignore")?;
+    }
+
+    Ok(())
+}
+
+/// Adds or updates the managed block in the '.gitattributes' of the project.
+pub(super) fn write_gitattributes<P>(project_dir: P) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let gitattributes_path = project_dir.as_ref().join(".gitattributes");
+    let gitattributes = if gitattributes_path.is_file() {
+        fs::read_to_string(&gitattributes_path).context("Failed to read .gitattributes")?
     } else {
-        info!("Creating .gitignore file...");
-        let mut file = File::create(gitignore_path).context("Failed to create .gitignore")?;
-        file.write_all(GIT_IGNORE.as_bytes())
-            .context("Failed to write to .gitignore")?;
+        String::new()
+    };
+    let updated_gitattributes = managed_block::upsert(&gitattributes, "#", GIT_ATTRIBUTES);
+
+    if updated_gitattributes != gitattributes {
+        info!("Writing .gitattributes file...");
+        fs::write(&gitattributes_path, updated_gitattributes)
+            .context("Failed to write to .gitattributes")?;
+    }
+
+    Ok(())
+}
+
+fn set_up_git_remote<P>(repo: &Repository, git_remote: P) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let git_remote = git_remote.as_ref();
+
+    // a missing or empty directory becomes a bare repository to push to
+    let is_empty_dir = git_remote.is_dir()
+        && git_remote
+            .read_dir()
+            .context("Failed to read git remote directory")?
+            .next()
+            .is_none();
+
+    if !git_remote.exists() || is_empty_dir {
+        info!("Creating bare git repository at {}", git_remote.display());
+        Repository::init_bare(git_remote).with_context(|| {
+            format!(
+                "Failed to create bare git repository at {}",
+                git_remote.display()
+            )
+        })?;
     }
 
+    let url = std::path::absolute(git_remote)
+        .context("Failed to get absolute path of git remote")?
+        .to_string_lossy()
+        .to_string();
+
+    info!("Setting git remote 'origin' to {url}");
+    match repo.find_remote("origin") {
+        Ok(_) => repo.remote_set_url("origin", &url),
+        Err(_) => repo.remote("origin", &url).map(|_| ()),
+    }
+    .context("Failed to set git remote")?;
+
+    Ok(())
+}
+
+fn initial_commit(repo: &Repository) -> Result<()> {
+    if repo.head().is_ok() {
+        info!("Project already has commits, skipping initial commit");
+        return Ok(());
+    }
+
+    info!("Making initial commit...");
+    let mut index = repo.index().context("Failed to get git index")?;
+    index
+        .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
+        .context("Failed to add files to git index")?;
+    index.write().context("Failed to write git index")?;
+
+    let tree = index.write_tree().context("Failed to write git tree")?;
+    let tree = repo.find_tree(tree).context("Failed to find git tree")?;
+
+    let signature = match repo.signature() {
+        Ok(signature) => signature,
+        Err(_) => {
+            warn!("Git user.name and user.email are not set, committing as {NAME}");
+            Signature::now(NAME, &format!("{NAME}@localhost"))
+                .context("Failed to create git signature")?
+        }
+    };
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "Initial commit",
+        &tree,
+        &[],
+    )
+    .context("Failed to make initial commit")?;
+
     Ok(())
 }
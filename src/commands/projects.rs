@@ -0,0 +1,73 @@
+use anyhow::{Context, Result};
+use log::{info, warn};
+
+use crate::{
+    cfg::Cfg,
+    game_build::GameBuild,
+    helper,
+    project_toml::{self, ProjectToml},
+};
+
+/// Lists the projects with their metadata.
+pub fn projects() -> Result<()> {
+    let cfg = helper::cfg_dir()?;
+    let cfg = Cfg::load(cfg)?;
+    let root_dir = helper::exe_dir()?;
+    let projects_dir = root_dir.join(&cfg.project_dir);
+    let build = GameBuild::from_root_dir(&root_dir, &cfg)?;
+
+    let project_dirs = helper::project_dirs(&root_dir, &cfg)?;
+
+    if project_dirs.is_empty() {
+        info!("No projects found in {}", projects_dir.display());
+    }
+
+    for project_dir in project_dirs {
+        let name = project_dir
+            .file_name()
+            .context("Failed to get project name")?
+            .to_string_lossy();
+
+        let project = match ProjectToml::load_from_path(project_dir.join(project_toml::FILE_NAME)) {
+            Ok(project) => project,
+            Err(err) => {
+                warn!("{name}: {err:#}");
+                continue;
+            }
+        };
+
+        info!("{name}");
+        for line in project.metadata() {
+            info!("  {line}");
+        }
+
+        let differences = project.verified_build_differences(&build);
+        if !differences.is_empty() {
+            warn!(
+                "  verified on a different {} build than installed",
+                differences.join(" and ")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Records the installed engine and Bunnymod XT build as the one the project is verified on.
+pub fn set_verified_build(project_name: &Option<String>) -> Result<()> {
+    let cfg = helper::cfg_dir()?;
+    let cfg = Cfg::load(cfg)?;
+    let root_dir = helper::exe_dir()?;
+    let project_dir = helper::project_dir_from_name(&root_dir, &cfg, project_name)?;
+    let project_toml = project_dir.join(project_toml::FILE_NAME);
+
+    let mut project = ProjectToml::load_from_path(&project_toml)?;
+    let build = GameBuild::from_root_dir(&root_dir, &cfg)?;
+
+    if build.engine_sha256.is_none() || build.bxt_sha256.is_none() {
+        warn!("The engine or Bunnymod XT isn't installed, only the installed parts are recorded");
+    }
+
+    project.verified_build = Some(build);
+    project.save_to_path(project_toml)
+}
@@ -1,20 +1,25 @@
 use core::str;
 use std::{
+    collections::HashSet,
     env::{current_dir, current_exe},
     ffi::OsStr,
-    path::Path,
+    fs, io,
+    path::{Path, PathBuf},
     process::{self, Output},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::{bail, Context, Result};
 use log::{debug, error, info, warn};
-use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System};
 
 use crate::{
     cfg::{self, Cfg},
+    commands::{hltas::seed, scratch, segments},
+    game_build::GameBuild,
     project_toml::{self, ProjectToml},
+    script,
 };
 
 pub struct RunGameMiscFlags {
@@ -33,6 +38,8 @@ pub struct RunGameFlags<'a> {
 
 pub struct RunGameBxtFlags<'a> {
     pub run_script: &'a Option<String>,
+    pub segment: &'a Option<String>,
+    pub seed: &'a Option<String>,
     pub optim_games: &'a Option<usize>,
     pub sim: bool,
     pub record: bool,
@@ -72,7 +79,7 @@ pub fn run_game(
     };
 
     info!("Loading project config...");
-    let project_toml = match project_dir {
+    let project_toml = match &project_dir {
         Some(project_dir) => Some(
             ProjectToml::load_from_path(project_dir.join(project_toml::FILE_NAME))
                 .context("Failed to load project config")?,
@@ -80,6 +87,50 @@ pub fn run_game(
         None => None,
     };
 
+    if let Some(project_toml) = &project_toml {
+        let build = GameBuild::from_root_dir(&root_dir, &cfg)?;
+        let differences = project_toml.verified_build_differences(&build);
+
+        if !differences.is_empty() {
+            warn!(
+                "The installed {} build differs from the one the project is verified on",
+                differences.join(" and ")
+            );
+        }
+    }
+
+    let run_script = match (run_game_bxt_flags.segment, &project_dir, &project_toml) {
+        (Some(segment), Some(project_dir), Some(project_toml)) => Some(segments::segment_script(
+            &root_dir,
+            project_dir,
+            &cfg,
+            project_toml,
+            segment,
+            true,
+        )?),
+        (Some(_), _, _) => bail!("Segments need to be run from the project directory"),
+        _ => run_game_bxt_flags.run_script.clone(),
+    };
+    let run_script = match (run_game_bxt_flags.seed, &project_dir, &project_toml) {
+        (Some(seed), Some(project_dir), Some(project_toml)) => {
+            let script = match (run_game_bxt_flags.segment, &run_script) {
+                (Some(segment), _) => project_dir.join(&project_toml.segment(segment)?.1.script),
+                (None, Some(run_script)) => {
+                    script_source(&root_dir, &cfg, project_dir, run_script)?
+                }
+                (None, None) => bail!("Running with a seed needs a script or a segment"),
+            };
+
+            Some(seeded_script(&root_dir, &cfg, project_dir, script, seed)?)
+        }
+        (Some(_), _, _) => bail!("Running with a seed needs to be done from the project directory"),
+        _ => run_script,
+    };
+    let run_game_bxt_flags = RunGameBxtFlags {
+        run_script: &run_script,
+        ..run_game_bxt_flags
+    };
+
     let r_input_exe = root_dir.join("RInput").join("RInput.exe");
 
     info!("Running game...");
@@ -108,6 +159,251 @@ pub fn run_game(
     Ok(())
 }
 
+/// Finds the file of a script passed by the name it is loaded with.
+fn script_source<P, P2>(
+    root_dir: P,
+    cfg: &Cfg,
+    project_dir: P2,
+    run_script: &str,
+) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let in_project = project_dir.as_ref().join(run_script);
+    let in_game_dir = root_dir.as_ref().join(&cfg.half_life_dir).join(run_script);
+
+    [in_project, in_game_dir]
+        .into_iter()
+        .find(|script| script.is_file())
+        .with_context(|| format!("Script {run_script} doesn't exist"))
+}
+
+/// Writes a copy of the script with the seed to the scratch directory and links it.
+///
+/// - The seed is `<shared> <non-shared>`, `<shared>` keeping the script's non-shared seed, or `random`.
+fn seeded_script<P, P2, P3>(
+    root_dir: P,
+    cfg: &Cfg,
+    project_dir: P2,
+    script: P3,
+    seed: &str,
+) -> Result<String>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+    P3: AsRef<Path>,
+{
+    let script = script.as_ref();
+    let contents = fs::read_to_string(script)
+        .with_context(|| format!("Failed to read {}", script.display()))?;
+    let hltas = script::parse(&contents)
+        .with_context(|| format!("Failed to parse {}", script.display()))?;
+
+    let seeds = if seed == "random" {
+        seed::random_seeds()
+    } else {
+        let non_shared = hltas.properties.seeds.map_or(0, |seeds| seeds.non_shared);
+        script::parse_seeds(seed, non_shared)?
+    };
+    let seeds = script::seeds_to_string(&seeds);
+    let contents = script::set_property(&contents, "seed", Some(&seeds))?;
+
+    let stem = script
+        .file_stem()
+        .context("Failed to get script name")?
+        .to_string_lossy();
+    let scratch = scratch::write_scratch(&project_dir, &format!("{stem}_seed.hltas"), &contents)?;
+
+    info!("Running {} with seed {seeds}", script.display());
+    scratch::link_scratch(root_dir, cfg, project_dir, scratch)
+}
+
+/// How long to wait for the game process of a launched instance to show up.
+const GAME_START_TIMEOUT: Duration = Duration::from_secs(30);
+/// An instance that ran at least this long before closing isn't counted as crash looping.
+const STABLE_RUN: Duration = Duration::from_secs(60);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// Instances that close this many times in a row without running for `STABLE_RUN` aren't restarted.
+const MAX_CRASHES: u32 = 5;
+
+/// A game started by optim mode, tracked by the PID of its hl.exe.
+struct OptimInstance {
+    number: usize,
+    pid: Option<Pid>,
+    started: Instant,
+    restarts: u32,
+    crashes: u32,
+    backoff: Duration,
+    next_start: Option<Instant>,
+    given_up: bool,
+}
+
+/// PIDs of the running hl.exe processes, a crashed game left as a zombie or dead process isn't running.
+fn hl_pids(system: &mut System) -> HashSet<Pid> {
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::new());
+    system
+        .processes_by_exact_name(OsStr::new("hl.exe"))
+        .filter(|process| {
+            !matches!(
+                process.status(),
+                ProcessStatus::Zombie | ProcessStatus::Dead
+            )
+        })
+        .map(|process| process.pid())
+        .collect()
+}
+
+/// Launches an instance and waits for its hl.exe to start, returns the PID of the new hl.exe.
+///
+/// - PIDs of games that were already running, started by hand or by other instances, are never taken.
+fn start_instance<F>(
+    system: &mut System,
+    instances: &[OptimInstance],
+    number: usize,
+    launch: &F,
+) -> Option<Pid>
+where
+    F: Fn() -> io::Result<()>,
+{
+    let known = hl_pids(system);
+
+    if let Err(err) = launch() {
+        error!("Failed to launch game {number}: {err}");
+        return None;
+    }
+
+    let waiting_since = Instant::now();
+    while waiting_since.elapsed() < GAME_START_TIMEOUT {
+        let new_pid = hl_pids(system).into_iter().find(|pid| {
+            !known.contains(pid) && !instances.iter().any(|instance| instance.pid == Some(*pid))
+        });
+
+        if let Some(pid) = new_pid {
+            info!("Game {number} started with PID {pid}");
+            return Some(pid);
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    error!(
+        "Game {number} didn't start within {} seconds",
+        GAME_START_TIMEOUT.as_secs()
+    );
+    None
+}
+
+/// Runs optim games, and with `keep_alive` restarts each one that closes.
+///
+/// - Each instance is restarted with a backoff that doubles every time it closes without running for a minute.
+/// - An instance that keeps closing right away is given up on after a few tries.
+fn run_optim_games<F>(optim_games: usize, keep_alive: bool, launch: F)
+where
+    F: Fn() -> io::Result<()>,
+{
+    let mut system = System::new();
+    let mut instances: Vec<OptimInstance> = Vec::new();
+
+    for number in 1..=optim_games {
+        let pid = start_instance(&mut system, &instances, number, &launch);
+        instances.push(OptimInstance {
+            number,
+            pid,
+            started: Instant::now(),
+            restarts: 0,
+            crashes: 0,
+            backoff: INITIAL_BACKOFF,
+            // a game that failed to start is retried like one that closed
+            next_start: pid.is_none().then(|| Instant::now() + INITIAL_BACKOFF),
+            given_up: false,
+        });
+    }
+
+    if !keep_alive {
+        return;
+    }
+
+    loop {
+        let running = hl_pids(&mut system);
+
+        for i in 0..instances.len() {
+            let instance = &mut instances[i];
+            if instance.given_up {
+                continue;
+            }
+
+            if let Some(pid) = instance.pid {
+                if running.contains(&pid) {
+                    continue;
+                }
+
+                instance.pid = None;
+                if instance.started.elapsed() >= STABLE_RUN {
+                    instance.crashes = 0;
+                    instance.backoff = INITIAL_BACKOFF;
+                } else {
+                    instance.crashes += 1;
+                }
+
+                if instance.crashes >= MAX_CRASHES {
+                    error!(
+                        "Game {} closed {} times in a row, not restarting it",
+                        instance.number, instance.crashes
+                    );
+                    instance.given_up = true;
+                    continue;
+                }
+
+                warn!(
+                    "Game {} (PID {pid}) closed, restarting it in {} seconds",
+                    instance.number,
+                    instance.backoff.as_secs()
+                );
+                instance.next_start = Some(Instant::now() + instance.backoff);
+                instance.backoff = (instance.backoff * 2).min(MAX_BACKOFF);
+                continue;
+            }
+
+            if instance
+                .next_start
+                .is_some_and(|next_start| Instant::now() >= next_start)
+            {
+                let number = instance.number;
+                let pid = start_instance(&mut system, &instances, number, &launch);
+
+                let instance = &mut instances[i];
+                instance.next_start = None;
+                instance.started = Instant::now();
+                match pid {
+                    Some(pid) => {
+                        instance.pid = Some(pid);
+                        instance.restarts += 1;
+                        info!("Restarted game {number} ({} restarts)", instance.restarts);
+                    }
+                    None => {
+                        instance.crashes += 1;
+                        if instance.crashes >= MAX_CRASHES {
+                            error!("Game {number} failed to start {} times in a row, not restarting it", instance.crashes);
+                            instance.given_up = true;
+                        } else {
+                            instance.next_start = Some(Instant::now() + instance.backoff);
+                            instance.backoff = (instance.backoff * 2).min(MAX_BACKOFF);
+                        }
+                    }
+                }
+            }
+        }
+
+        if instances.iter().all(|instance| instance.given_up) {
+            error!("All games were given up on");
+            return;
+        }
+
+        thread::sleep(Duration::from_secs(5));
+    }
+}
+
 fn run_r_input<P>(r_input_exe: P) -> Result<Option<Output>>
 where
     P: AsRef<Path>,
@@ -147,6 +443,8 @@ where
     } = run_game_flags;
     let RunGameBxtFlags {
         run_script,
+        segment: _,
+        seed: _,
         optim_games,
         sim,
         record,
@@ -245,7 +543,7 @@ where
     } else {
         match optim_games {
             Some(optim_games) => {
-                let run_game = |out_of, total_games| {
+                let launch = || {
                     #[cfg(target_os = "linux")]
                     let bxt_result = process::Command::new(wine_exe)
                         .arg(&injector_exe)
@@ -262,70 +560,10 @@ where
                         .envs(std::env::vars())
                         .output();
 
-                    match bxt_result {
-                        Ok(_) => info!(
-                            "Successfully launched {} out of {} games",
-                            out_of, total_games
-                        ),
-                        // TODO attempt to start the game again if it fails
-                        Err(err) => error!(
-                            "Failed to launch {} out of {} games: {}",
-                            out_of, total_games, err
-                        ),
-                    }
+                    bxt_result.map(|_| ())
                 };
-                let hl_exe_count = |system: &mut System| {
-                    // TODO: track pids instead
-                    system.refresh_processes_specifics(
-                        ProcessesToUpdate::All,
-                        true,
-                        ProcessRefreshKind::new().with_cpu(),
-                    );
-                    system.processes_by_exact_name(OsStr::new("hl.exe")).count()
-                };
-
-                let mut system = System::new();
-                // we get initial half life count
-                let initial_hl_count = hl_exe_count(&mut system);
-
-                for i in 0..*optim_games {
-                    run_game(i + 1, *optim_games);
-
-                    // TODO wait for the game to start in a better way
-                    thread::sleep(Duration::from_secs(6));
-                }
-
-                if *keep_alive {
-                    loop {
-                        // TODO: track pids instead
-                        system.refresh_processes_specifics(
-                            ProcessesToUpdate::All,
-                            true,
-                            ProcessRefreshKind::new().with_cpu(),
-                        );
-
-                        // scan for hl.exe processes
-                        let current_lives = hl_exe_count(&mut system);
-                        let expected_lives_count = initial_hl_count + *optim_games;
-
-                        if current_lives < expected_lives_count {
-                            let missing_half_lives = expected_lives_count - current_lives;
-                            warn!(
-                                "Missing {} games, starting up new half-lives",
-                                missing_half_lives
-                            );
-
-                            for i in 0..missing_half_lives {
-                                run_game(i + 1, missing_half_lives);
-
-                                // TODO wait for the game to start in a better way
-                                thread::sleep(Duration::from_secs(6));
-                            }
-                        }
 
-                        thread::sleep(Duration::from_secs(5));
-                    }
-                }
+                run_optim_games(*optim_games, *keep_alive, launch);
 
                 None
             }
@@ -0,0 +1,131 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+
+use crate::{cfg::Cfg, commands::link_hltas::script_link_path, helper};
+
+/// Directory in a project for generated scripts, ignored by git and by script discovery.
+pub const SCRATCH_DIR: &str = ".scratch";
+
+/// Writes a generated script into the scratch directory of the project.
+///
+/// - `file_name` can have sub directories in it.
+pub fn write_scratch<P>(project_dir: P, file_name: &str, contents: &str) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let path = project_dir.as_ref().join(SCRATCH_DIR).join(file_name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(path)
+}
+
+fn game_dirs(root_dir: &Path, cfg: &Cfg) -> Vec<PathBuf> {
+    let mut game_dirs = vec![root_dir.join(&cfg.half_life_dir)];
+    if let Some(no_client_dll_dir) = &cfg.no_client_dll_dir {
+        game_dirs.push(root_dir.join(no_client_dll_dir));
+    }
+
+    game_dirs
+}
+
+/// Removes a directory of scratch scripts along with their links in the game directories.
+pub fn remove_scratch_dir<P, P2>(root_dir: P, cfg: &Cfg, project_dir: P2, dir: &str) -> Result<()>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let root_dir = root_dir.as_ref();
+    let project_dir = project_dir.as_ref();
+    let scratch_dir = project_dir.join(SCRATCH_DIR).join(dir);
+    if !scratch_dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in scratch_dir
+        .read_dir()
+        .with_context(|| format!("Failed to read {}", scratch_dir.display()))?
+    {
+        let scratch = entry.context("Failed to read scratch script")?.path();
+        if !scratch.is_file() {
+            continue;
+        }
+        let link = script_link_path(cfg, project_dir, &scratch)?;
+        let contents =
+            fs::read(&scratch).with_context(|| format!("Failed to read {}", scratch.display()))?;
+
+        for game_dir in game_dirs(root_dir, cfg) {
+            let game_dir_hltas = game_dir.join(&link);
+            if !game_dir_hltas.is_file() {
+                continue;
+            }
+
+            // a link shares the contents of the scratch script, anything else isn't ours
+            let game_dir_contents = fs::read(&game_dir_hltas)
+                .with_context(|| format!("Failed to read {}", game_dir_hltas.display()))?;
+            if game_dir_contents != contents {
+                warn!(
+                    "{} is not a link of {}, leaving it",
+                    game_dir_hltas.display(),
+                    scratch.display()
+                );
+                continue;
+            }
+
+            debug!("Removing {}", game_dir_hltas.display());
+            fs::remove_file(&game_dir_hltas)
+                .with_context(|| format!("Failed to remove {}", game_dir_hltas.display()))?;
+        }
+    }
+
+    fs::remove_dir_all(&scratch_dir)
+        .with_context(|| format!("Failed to remove {}", scratch_dir.display()))
+}
+
+/// Links a scratch script into the game directories and returns the name to load it with.
+///
+/// - The links aren't recorded in the link registry, the next scratch script of the same name replaces them.
+pub fn link_scratch<P, P2, P3>(
+    root_dir: P,
+    cfg: &Cfg,
+    project_dir: P2,
+    scratch: P3,
+) -> Result<String>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+    P3: AsRef<Path>,
+{
+    let root_dir = root_dir.as_ref();
+    let scratch = scratch.as_ref();
+    let link = script_link_path(cfg, project_dir, scratch)?;
+
+    for game_dir in game_dirs(root_dir, cfg) {
+        let game_dir_hltas = game_dir.join(&link);
+        debug!(
+            "Linking {} to {}",
+            scratch.display(),
+            game_dir_hltas.display()
+        );
+
+        if let Some(parent) = game_dir_hltas.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        helper::force_link(scratch, &game_dir_hltas).context("Failed to hard link hltas")?;
+    }
+
+    Ok(link
+        .to_str()
+        .context("Script path is not valid unicode")?
+        .replace('\\', "/"))
+}
@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+
+use crate::{
+    cfg::Cfg,
+    commands::link_hltas::{link_hltas_once, script_link_path},
+    helper,
+    project_toml::{self, ProjectToml},
+};
+
+/// Save dirs of both clients that don't have the save.
+fn missing_save_dirs<P>(root_dir: P, cfg: &Cfg, game: &str, save: &str) -> Vec<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let file_name = helper::save_file_name(save);
+
+    helper::save_dirs(root_dir, cfg, game)
+        .into_iter()
+        .filter(|save_dir| !save_dir.join(&file_name).is_file())
+        .collect()
+}
+
+/// Checks that every segment has its script and its start save in both clients.
+///
+/// - Also warns when a segment doesn't start from the save the previous one ends with.
+pub fn check_segments(project_name: &Option<String>) -> Result<()> {
+    let cfg = helper::cfg_dir()?;
+    let cfg = Cfg::load(cfg)?;
+    let root_dir = helper::exe_dir()?;
+    let project_dir = helper::project_dir_from_name(&root_dir, &cfg, project_name)?;
+    let project = ProjectToml::load_from_path(project_dir.join(project_toml::FILE_NAME))?;
+
+    if project.segments.is_empty() {
+        bail!("The project has no segments\nHelp: Add [[segments]] to project.toml");
+    }
+
+    let mut problems = 0;
+    let mut previous_end_save = None;
+
+    for (i, segment) in project.segments.iter().enumerate() {
+        let name = format!("{} {}", i + 1, segment.name);
+        let map = segment
+            .map
+            .as_ref()
+            .map(|map| format!(" ({map})"))
+            .unwrap_or_default();
+
+        match &segment.start_save {
+            Some(start_save) => info!("{name}{map}: starts from save '{start_save}'"),
+            None => info!("{name}{map}: starts from the map"),
+        }
+
+        if !project_dir.join(&segment.script).is_file() {
+            warn!("  script {} doesn't exist", segment.script.display());
+            problems += 1;
+        }
+
+        if let Some(start_save) = &segment.start_save {
+            for save_dir in missing_save_dirs(&root_dir, &cfg, &project.game, start_save) {
+                warn!(
+                    "  start save '{start_save}' is missing in {}",
+                    save_dir.display()
+                );
+                problems += 1;
+            }
+        }
+
+        if let Some(previous_end_save) = previous_end_save {
+            if segment.start_save.as_ref() != Some(previous_end_save) {
+                warn!("  doesn't start from '{previous_end_save}' that the previous segment ends with");
+            }
+        }
+        previous_end_save = segment.end_save.as_ref();
+    }
+
+    if problems > 0 {
+        bail!("Found {problems} problems with the segments");
+    }
+
+    Ok(())
+}
+
+/// Links the scripts of the project and returns the name to load the segment's script with.
+///
+/// - `interactive` is passed on to linking, so `diverged_scripts = "ask"` can ask.
+pub fn segment_script<P, P2>(
+    root_dir: P,
+    project_dir: P2,
+    cfg: &Cfg,
+    project: &ProjectToml,
+    segment: &str,
+    interactive: bool,
+) -> Result<String>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let project_dir = project_dir.as_ref();
+    let (i, segment) = project.segment(segment)?;
+    let script = project_dir.join(&segment.script);
+
+    if !script.is_file() {
+        bail!(
+            "Script {} of segment {} doesn't exist",
+            script.display(),
+            segment.name
+        );
+    }
+
+    if let Some(start_save) = &segment.start_save {
+        for save_dir in missing_save_dirs(root_dir, cfg, &project.game, start_save) {
+            warn!(
+                "Start save '{start_save}' of segment {} is missing in {}",
+                segment.name,
+                save_dir.display()
+            );
+        }
+    }
+
+    link_hltas_once(true, project_dir, cfg, true, interactive, false)?;
+
+    info!("Running segment {} {}", i + 1, segment.name);
+    let link = script_link_path(cfg, project_dir, &script)?;
+    let link = link
+        .to_str()
+        .context("Script path is not valid unicode")?
+        .replace('\\', "/");
+
+    Ok(link)
+}
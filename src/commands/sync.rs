@@ -1,11 +1,11 @@
-use std::{env::current_dir, path::Path, thread, time::Duration};
+use std::{collections::HashMap, env::current_dir, path::Path, thread, time::Duration};
 
 use log::{debug, info};
 
 use crate::{
     cfg::{self, Cfg},
     commands::{link_hltas::link_hltas_once, sync_saves::sync_saves_once},
-    project_toml,
+    history, project_toml,
 };
 use anyhow::{Context, Result};
 
@@ -36,8 +36,20 @@ pub fn sync() -> Result<()> {
         root_dir.display(),
     );
 
+    let project_dir = project_toml_path.is_file().then_some(current_dir.as_path());
+    let mut modified_times = HashMap::new();
+
     loop {
-        link_hltas_once(project_toml_path.is_file(), &current_dir, &cfg, true)?;
+        history::snapshot_changed(project_dir, root_dir, &cfg, &mut modified_times);
+
+        link_hltas_once(
+            project_toml_path.is_file(),
+            &current_dir,
+            &cfg,
+            true,
+            false,
+            false,
+        )?;
 
         if let Some(no_client_dll_dir) = &cfg.no_client_dll_dir {
             sync_saves_once(
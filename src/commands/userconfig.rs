@@ -0,0 +1,152 @@
+use std::{
+    env::current_dir,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use log::info;
+
+use crate::{
+    cfg::Cfg,
+    cli::UserconfigAction,
+    helper, managed_block,
+    project_toml::{self, ProjectToml},
+    DEFAULT_GAME,
+};
+
+const USERCONFIG: &str = "userconfig.cfg";
+const COMMENT: &str = "//";
+const CONFIG: &str = "exec hltas.cfg\nloadtas2\n";
+
+/// Lines that older versions appended without a managed block, always together and in this order.
+const LEGACY_LINES: [&str; 2] = ["exec hltas.cfg", "loadtas2"];
+
+fn remove_legacy_lines(contents: &str) -> String {
+    managed_block::edit_outside(contents, COMMENT, |text| {
+        managed_block::strip_runs(text, &LEGACY_LINES).0
+    })
+}
+
+/// Adds or updates the managed block in the userconfig.cfg of the game dir.
+pub fn enable<P>(game_dir: P) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let userconfig_path = game_dir.as_ref().join(USERCONFIG);
+    let contents = read_userconfig(&userconfig_path)?;
+    let updated = managed_block::upsert(&remove_legacy_lines(&contents), COMMENT, CONFIG);
+
+    write_if_changed(&userconfig_path, &contents, &updated)
+}
+
+/// Removes the managed block from the userconfig.cfg of the game dir.
+pub fn disable<P>(game_dir: P) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let userconfig_path = game_dir.as_ref().join(USERCONFIG);
+    if !userconfig_path.is_file() {
+        return Ok(());
+    }
+
+    let contents = read_userconfig(&userconfig_path)?;
+    let updated = managed_block::remove(&remove_legacy_lines(&contents), COMMENT);
+
+    write_if_changed(&userconfig_path, &contents, &updated)
+}
+
+fn read_userconfig(path: &Path) -> Result<String> {
+    if path.is_file() {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+    } else {
+        Ok(String::new())
+    }
+}
+
+fn write_if_changed(path: &Path, contents: &str, updated: &str) -> Result<()> {
+    if contents == updated {
+        info!("{} is already up to date", path.display());
+        return Ok(());
+    }
+
+    info!("Writing {}", path.display());
+    fs::write(path, updated).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn status(game_dir: &Path) -> Result<String> {
+    let userconfig_path = game_dir.join(USERCONFIG);
+    if !userconfig_path.is_file() {
+        return Ok("disabled (no userconfig.cfg)".to_string());
+    }
+
+    let contents = read_userconfig(&userconfig_path)?;
+    let mut status = match managed_block::get(&contents, COMMENT) {
+        Some(block) if block.lines().eq(CONFIG.lines()) => "enabled".to_string(),
+        Some(_) => "enabled (outdated)".to_string(),
+        None => "disabled".to_string(),
+    };
+
+    let (_, legacy_copies) =
+        managed_block::strip_runs(&managed_block::remove(&contents, COMMENT), &LEGACY_LINES);
+    if legacy_copies > 0 {
+        status.push_str(&format!(
+            ", {legacy_copies} unmanaged copies of the lines from older versions"
+        ));
+    }
+
+    Ok(status)
+}
+
+/// Returns the game dirs of both clients that exist.
+fn game_dirs<P>(root_dir: P, cfg: &Cfg, game: &str) -> Vec<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let root_dir = root_dir.as_ref();
+    let mut game_dirs = vec![root_dir.join(&cfg.half_life_dir).join(game)];
+
+    if let Some(no_client_dll_dir) = &cfg.no_client_dll_dir {
+        game_dirs.push(root_dir.join(no_client_dll_dir).join(game));
+    }
+
+    game_dirs.into_iter().filter(|dir| dir.is_dir()).collect()
+}
+
+pub fn userconfig(action: UserconfigAction, game: &Option<String>) -> Result<()> {
+    let cfg = helper::cfg_dir()?;
+    let cfg = Cfg::load(cfg)?;
+    let root_dir = helper::exe_dir()?;
+
+    let game = match game {
+        Some(game) => game.to_owned(),
+        None => {
+            let project_toml = current_dir()
+                .context("Failed to get current directory")?
+                .join(project_toml::FILE_NAME);
+
+            if project_toml.is_file() {
+                ProjectToml::load_from_path(project_toml)?.game
+            } else {
+                DEFAULT_GAME.to_string()
+            }
+        }
+    };
+
+    let game_dirs = game_dirs(&root_dir, &cfg, &game);
+    if game_dirs.is_empty() {
+        bail!("Game '{game}' not found");
+    }
+
+    for game_dir in game_dirs {
+        match action {
+            UserconfigAction::Show => {
+                info!("{}: {}", game_dir.display(), status(&game_dir)?);
+            }
+            UserconfigAction::Enable => enable(&game_dir)?,
+            UserconfigAction::Disable => disable(&game_dir)?,
+        }
+    }
+
+    Ok(())
+}
@@ -5,73 +5,100 @@ use std::{
 };
 
 use anyhow::{bail, Context, Result};
-use lazy_static::lazy_static;
 use log::info;
-use sha2::Digest;
 
-use crate::{cfg::Cfg, helper};
+use crate::{helper, managed_block};
 
-const HARD_LINK_POST_CHECKOUT_HOOK: &str = include_str!("../resource/git_hooks/post-checkout");
-lazy_static! {
-    static ref HARD_LINK_POST_CHECKOUT_HOOK_SHA_256: Vec<u8> = {
-        let mut hasher = sha2::Sha256::new();
-        hasher.update(HARD_LINK_POST_CHECKOUT_HOOK);
-        hasher.finalize().to_vec()
-    };
+/// Hooks that relink the scripts after git changes the files in the working tree.
+pub const GIT_HOOKS: &[&str] = &["post-checkout", "post-merge", "post-rewrite"];
+
+/// Start and end of the bash hook that older versions installed.
+const LEGACY_HOOK_START: &str = "#!/bin/bash\n\nbash_dir=$(cd \"$(dirname \"$0\")\" && pwd)\n";
+const LEGACY_HOOK_END: &str = "\ndone";
+
+/// Removes every copy of the legacy bash hook.
+fn remove_legacy_hook(hook: &str) -> String {
+    let mut hook = hook.to_string();
+
+    while let Some(start) = hook.find(LEGACY_HOOK_START) {
+        let end = match hook[start..].find(LEGACY_HOOK_END) {
+            Some(end) => start + end + LEGACY_HOOK_END.len(),
+            None => hook.len(),
+        };
+        let end = if hook[end..].starts_with('\n') {
+            end + 1
+        } else {
+            end
+        };
+
+        hook.replace_range(start..end, "");
+    }
+
+    hook
 }
 
-pub fn write_hard_link_shell_hook<P>(path: P, cfg: &Cfg) -> Result<()>
+/// Writes a git hook that calls the manager to link the scripts.
+///
+/// - The call is kept in a managed block so other contents of the hook are kept.
+/// - Hooks installed by older versions are replaced in place.
+pub fn write_git_hook<P>(path: P) -> Result<()>
 where
     P: AsRef<Path>,
 {
     let path = path.as_ref();
+    let hook_name = path
+        .file_name()
+        .context("Failed to get hook name")?
+        .to_string_lossy();
 
-    let mut file = if path.is_file() {
-        // append to existing hook unless it is the same
-        if helper::sha_256_file(path).context("Failed to get sha256 of post-checkout hook")?
-            == *HARD_LINK_POST_CHECKOUT_HOOK_SHA_256
-        {
-            info!("Post-checkout hook is already installed");
-            return Ok(());
-        } else {
-            fs::OpenOptions::new()
-                .append(true)
-                .open(path)
-                .context("Failed to open ./git/hooks/post-checkout")?
-        }
+    let exe = helper::git_exe_path()?;
+    let block = format!("\"{exe}\" link-hltas\n");
+
+    let hook = if path.is_file() {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {hook_name} hook"))?
     } else {
-        // create new file
-        File::create(path).context("Failed to create ./git/hooks/post-checkout")?
+        String::new()
     };
 
-    let hook = {
-        let mut hook = HARD_LINK_POST_CHECKOUT_HOOK.replace(
-            "HALF_LIFE_DIR",
-            &cfg.half_life_dir
-                .file_name()
-                .context("Failed to get half-life dir name")?
-                .to_string_lossy(),
-        );
-
-        let no_client_dll_present = "NO_CLIENT_DLL_PRESENT";
-
-        hook = match &cfg.no_client_dll_dir {
-            Some(no_client_dll_dir) => hook.replace(no_client_dll_present, "true").replace(
-                "NO_CLIENT_DLL_DIR",
-                &no_client_dll_dir
-                    .file_name()
-                    .context("Failed to get no-client-dll dir name")?
-                    .to_string_lossy(),
-            ),
-            None => hook.replace(no_client_dll_present, "false"),
-        };
+    let mut updated = remove_legacy_hook(&hook);
+    if !updated.starts_with("#!") {
+        updated.insert_str(0, "#!/bin/sh\n");
+    }
+    let updated = managed_block::upsert(&updated, "#", &block);
 
-        hook
-    };
+    if updated == hook {
+        info!("{hook_name} hook is already installed");
+    } else {
+        info!("Installing {hook_name} hook");
+        fs::write(path, updated).with_context(|| format!("Failed to write {hook_name} hook"))?;
+    }
 
-    info!("Installing post-checkout hook");
-    file.write_all(hook.as_bytes())
-        .context("Failed to write hard-link hook to ./git/hooks/post-checkout")?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut permissions = fs::metadata(path)
+            .with_context(|| format!("Failed to get permissions of {hook_name} hook"))?
+            .permissions();
+        permissions.set_mode(permissions.mode() | 0o111);
+        fs::set_permissions(path, permissions)
+            .with_context(|| format!("Failed to make {hook_name} hook executable"))?;
+    }
+
+    Ok(())
+}
+
+/// Writes all the hooks in [`GIT_HOOKS`] to the hooks dir.
+pub fn write_git_hooks<P>(hooks_dir: P) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let hooks_dir = hooks_dir.as_ref();
+    fs::create_dir_all(hooks_dir).context("Failed to create git hooks directory")?;
+
+    for hook in GIT_HOOKS {
+        write_git_hook(hooks_dir.join(hook))?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{cfg::Cfg, helper};
+
+pub const ENGINE_DLL: &str = "hw.dll";
+pub const BXT_DIR: &str = "Bunnymod XT";
+pub const BXT_DLL: &str = "BunnymodXT.dll";
+
+/// Identifies the engine and Bunnymod XT builds by the sha256 of their dlls.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameBuild {
+    pub engine_sha256: Option<String>,
+    pub bxt_sha256: Option<String>,
+}
+
+impl GameBuild {
+    /// Reads the build of the installed engine and Bunnymod XT.
+    ///
+    /// - Missing dlls are left as `None`.
+    pub fn from_root_dir<P>(root_dir: P, cfg: &Cfg) -> Result<GameBuild>
+    where
+        P: AsRef<Path>,
+    {
+        let root_dir = root_dir.as_ref();
+
+        let hash_if_exists = |path: &Path| -> Result<Option<String>> {
+            if path.is_file() {
+                Ok(Some(helper::to_hex(&helper::sha_256_file(path)?)))
+            } else {
+                Ok(None)
+            }
+        };
+
+        Ok(GameBuild {
+            engine_sha256: hash_if_exists(&root_dir.join(&cfg.half_life_dir).join(ENGINE_DLL))?,
+            bxt_sha256: hash_if_exists(&root_dir.join(BXT_DIR).join(BXT_DLL))?,
+        })
+    }
+
+    /// Lists the parts of the build that differ from `other`.
+    ///
+    /// - Parts that are unknown on either side are not compared.
+    pub fn differences(&self, other: &GameBuild) -> Vec<&'static str> {
+        let mut differences = Vec::new();
+
+        let differs = |a: &Option<String>, b: &Option<String>| match (a, b) {
+            (Some(a), Some(b)) => a != b,
+            _ => false,
+        };
+
+        if differs(&self.engine_sha256, &other.engine_sha256) {
+            differences.push("engine");
+        }
+        if differs(&self.bxt_sha256, &other.bxt_sha256) {
+            differences.push("Bunnymod XT");
+        }
+
+        differences
+    }
+}
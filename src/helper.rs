@@ -1,12 +1,18 @@
 use std::{
-    fs, io,
+    fs,
+    io::{self, BufRead, Write},
     path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{bail, Context, Result};
 use sha2::Digest;
 
-use crate::cfg;
+use crate::{
+    cfg::{self, Cfg},
+    project_toml,
+};
 
 pub fn try_root_dir() -> Result<PathBuf> {
     let working_dir = std::env::current_dir().context("Failed to get current dir")?;
@@ -62,3 +68,199 @@ pub fn force_link<P: AsRef<Path>, Q: AsRef<Path>>(original: P, link: Q) -> io::R
     }
     fs::hard_link(original, link)
 }
+
+/// Identifies a file on its volume, hard links to the same file have the same id.
+///
+/// - The device and inode number on unix.
+#[cfg(unix)]
+pub fn file_id<P>(path: P) -> Result<String>
+where
+    P: AsRef<Path>,
+{
+    use std::os::unix::fs::MetadataExt;
+
+    let path = path.as_ref();
+    let metadata =
+        fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    Ok(format!("{}:{}", metadata.dev(), metadata.ino()))
+}
+
+/// Identifies a file on its volume, hard links to the same file have the same id.
+///
+/// - The volume serial number and file index on Windows.
+#[cfg(windows)]
+pub fn file_id<P>(path: P) -> Result<String>
+where
+    P: AsRef<Path>,
+{
+    use std::{mem, os::windows::io::AsRawHandle};
+
+    use winapi::um::fileapi::{GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION};
+
+    let path = path.as_ref();
+    let file =
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+    // SAFETY: the handle stays open during the call and `info` is a valid struct to fill in
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { mem::zeroed() };
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle().cast(), &mut info) } == 0 {
+        return Err(io::Error::last_os_error())
+            .with_context(|| format!("Failed to read {}", path.display()));
+    }
+
+    let index = (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow);
+    Ok(format!("{}:{index}", info.dwVolumeSerialNumber))
+}
+
+/// Asks a yes or no question on the terminal, anything but yes is a no.
+pub fn confirm(question: &str) -> Result<bool> {
+    print!("{question} [y/N] ");
+    io::stdout().flush().context("Failed to write to stdout")?;
+
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .context("Failed to read answer")?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// A random number for seeds, from the sha256 of the current time, the process id and a counter.
+///
+/// - The counter makes calls within the same clock tick differ.
+/// - This is not suitable for anything that needs to be unpredictable, it only spreads seeds.
+pub fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(time.to_le_bytes());
+    hasher.update(std::process::id().to_le_bytes());
+    hasher.update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+
+    let hash = hasher.finalize();
+    u64::from_le_bytes(hash[..8].try_into().unwrap())
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn sha_256_hex(bytes: &[u8]) -> String {
+    let mut hasher = sha2::Sha256::new();
+
+    hasher.update(bytes);
+
+    to_hex(&hasher.finalize())
+}
+
+/// Returns the `SAVE` dirs of `game` in both clients, the main client first.
+pub fn save_dirs<P>(root_dir: P, cfg: &Cfg, game: &str) -> Vec<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let root_dir = root_dir.as_ref();
+    let mut dirs = vec![root_dir.join(&cfg.half_life_dir).join(game).join("SAVE")];
+
+    if let Some(no_client_dll_dir) = &cfg.no_client_dll_dir {
+        dirs.push(root_dir.join(no_client_dll_dir).join(game).join("SAVE"));
+    }
+
+    dirs
+}
+
+/// File name of a save as passed to `load` or `save`.
+pub fn save_file_name(save: &str) -> String {
+    if save.ends_with(".sav") {
+        save.to_string()
+    } else {
+        format!("{save}.sav")
+    }
+}
+
+/// Path of the manager exe for git to run, git's shell wants forward slashes.
+pub fn git_exe_path() -> Result<String> {
+    let exe = std::env::current_exe().context("Failed to get current exe path")?;
+
+    Ok(exe.to_string_lossy().replace('\\', "/"))
+}
+
+/// Returns the project directory from the project name, or the current directory if it is a project.
+pub fn project_dir_from_name<P>(
+    root_dir: P,
+    cfg: &Cfg,
+    project_name: &Option<String>,
+) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let project_dir = match project_name {
+        Some(project_name) => root_dir.as_ref().join(&cfg.project_dir).join(project_name),
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+
+    if !project_dir.join(project_toml::FILE_NAME).is_file() {
+        bail!(
+            "{} is not a project\nHelp: Pass the project name or run from the project directory",
+            project_dir.display()
+        );
+    }
+
+    Ok(project_dir)
+}
+
+/// Formats seconds as `m:ss.mmm`, or `h:mm:ss.mmm` if it's over an hour.
+pub fn format_time(seconds: f64) -> String {
+    let sign = if seconds < 0. { "-" } else { "" };
+    let millis = (seconds.abs() * 1000.).round() as u64;
+    let (hours, minutes) = (millis / 3_600_000, millis / 60_000 % 60);
+    let (secs, millis) = (millis / 1000 % 60, millis % 1000);
+
+    if hours > 0 {
+        format!("{sign}{hours}:{minutes:02}:{secs:02}.{millis:03}")
+    } else {
+        format!("{sign}{minutes}:{secs:02}.{millis:03}")
+    }
+}
+
+/// Lists the project directories that have a project.toml, sorted by name.
+pub fn project_dirs<P>(root_dir: P, cfg: &Cfg) -> Result<Vec<PathBuf>>
+where
+    P: AsRef<Path>,
+{
+    let projects_dir = root_dir.as_ref().join(&cfg.project_dir);
+
+    let mut project_dirs = Vec::new();
+    for project in projects_dir
+        .read_dir()
+        .context("Failed to read project dir")?
+    {
+        let path = project.context("Failed to read project file")?.path();
+
+        if path.join(project_toml::FILE_NAME).is_file() {
+            project_dirs.push(path);
+        }
+    }
+    project_dirs.sort();
+
+    Ok(project_dirs)
+}
+
+/// The current project, or all projects if run from the root dir.
+pub fn current_project_dirs() -> Result<Vec<PathBuf>> {
+    let current_dir = std::env::current_dir().context("Failed to get current directory")?;
+
+    if current_dir.join(project_toml::FILE_NAME).is_file() {
+        return Ok(vec![current_dir]);
+    }
+
+    let root_dir = try_root_dir()?;
+    let cfg = Cfg::load(root_dir.join(cfg::cfg_file_name())).context("Failed to load cfg")?;
+
+    project_dirs(root_dir, &cfg)
+}
@@ -0,0 +1,258 @@
+//! Local snapshots of scripts, kept independent of git.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+
+use crate::{cfg::Cfg, commands::link_hltas::hltases_from_dir, helper, project_toml};
+
+/// Directory in a project that snapshots are kept in, ignored by git and by script discovery.
+pub const HISTORY_DIR: &str = ".history";
+
+const SNAPSHOT_EXTENSION: &str = "hltas";
+
+pub struct Snapshot {
+    pub path: PathBuf,
+    /// The UTC time the snapshot was taken, as `YYYYMMDD-HHMMSS-mmm`.
+    pub name: String,
+    pub time: SystemTime,
+}
+
+/// Finds the project a script is in from its parent directories.
+pub fn project_dir_of<P>(script: P) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let script = script.as_ref();
+    let script = script
+        .canonicalize()
+        .with_context(|| format!("Failed to find {}", script.display()))?;
+
+    match script
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join(project_toml::FILE_NAME).is_file())
+    {
+        Some(project_dir) => Ok(project_dir.to_path_buf()),
+        None => bail!("{} is not in a project", script.display()),
+    }
+}
+
+/// Directory of the snapshots of a script, at the path of the script in the project.
+pub fn snapshot_dir<P, P2>(project_dir: P, script: P2) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let project_dir = project_dir.as_ref();
+    let script = script.as_ref();
+    let relative = script.strip_prefix(project_dir).with_context(|| {
+        format!(
+            "{} is not in the project {}",
+            script.display(),
+            project_dir.display()
+        )
+    })?;
+
+    Ok(project_dir.join(HISTORY_DIR).join(relative))
+}
+
+/// The snapshots of a script, newest first.
+pub fn snapshots<P, P2>(project_dir: P, script: P2) -> Result<Vec<Snapshot>>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let snapshot_dir = snapshot_dir(project_dir, script)?;
+    if !snapshot_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in snapshot_dir
+        .read_dir()
+        .with_context(|| format!("Failed to read {}", snapshot_dir.display()))?
+    {
+        let path = entry.context("Failed to read snapshot")?.path();
+        if path.extension().is_none_or(|ext| ext != SNAPSHOT_EXTENSION) {
+            continue;
+        }
+
+        let name = path
+            .file_stem()
+            .context("Failed to get snapshot name")?
+            .to_string_lossy()
+            .to_string();
+        let time = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        snapshots.push(Snapshot { path, name, time });
+    }
+
+    // the names sort by the time they were taken
+    snapshots.sort_by(|a, b| b.name.cmp(&a.name));
+
+    Ok(snapshots)
+}
+
+/// Year, month and day of a day since the unix epoch.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month as u32, day as u32)
+}
+
+fn snapshot_name(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let (hours, minutes, seconds) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+
+    format!(
+        "{year:04}{month:02}{day:02}-{hours:02}{minutes:02}{seconds:02}-{:03}",
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Saves a copy of the script if it differs from its newest snapshot, returns the new snapshot.
+pub fn snapshot<P, P2>(project_dir: P, script: P2) -> Result<Option<PathBuf>>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let project_dir = project_dir.as_ref();
+    let script = script.as_ref();
+    let contents =
+        fs::read(script).with_context(|| format!("Failed to read {}", script.display()))?;
+
+    if let Some(newest) = snapshots(project_dir, script)?.first() {
+        let newest = fs::read(&newest.path)
+            .with_context(|| format!("Failed to read {}", newest.path.display()))?;
+        if newest == contents {
+            return Ok(None);
+        }
+    }
+
+    let snapshot_dir = snapshot_dir(project_dir, script)?;
+    fs::create_dir_all(&snapshot_dir)
+        .with_context(|| format!("Failed to create {}", snapshot_dir.display()))?;
+
+    let path = snapshot_dir
+        .join(snapshot_name(SystemTime::now()))
+        .with_extension(SNAPSHOT_EXTENSION);
+    fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(Some(path))
+}
+
+/// Removes the snapshots past `limit` and the ones older than `days`, but never the newest one.
+///
+/// - Returns how many snapshots were removed.
+pub fn prune<P, P2>(project_dir: P, script: P2, limit: usize, days: u64) -> Result<usize>
+where
+    P: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let max_age = Duration::from_secs(days * 86_400);
+    let now = SystemTime::now();
+    let mut removed = 0;
+
+    for (i, snapshot) in snapshots(project_dir, script)?.iter().enumerate().skip(1) {
+        let too_old = now
+            .duration_since(snapshot.time)
+            .is_ok_and(|age| age > max_age);
+
+        if i >= limit || too_old {
+            fs::remove_file(&snapshot.path)
+                .with_context(|| format!("Failed to remove {}", snapshot.path.display()))?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+fn snapshot_changed_projects(
+    project_dir: Option<&Path>,
+    root_dir: &Path,
+    cfg: &Cfg,
+    modified_times: &mut HashMap<PathBuf, SystemTime>,
+) -> Result<()> {
+    let project_dirs = match project_dir {
+        Some(project_dir) => vec![project_dir.to_path_buf()],
+        None => helper::project_dirs(root_dir, cfg)?,
+    };
+
+    for project_dir in &project_dirs {
+        for hltas in hltases_from_dir(project_dir)? {
+            let Ok(modified) = fs::metadata(&hltas).and_then(|metadata| metadata.modified()) else {
+                continue;
+            };
+            if modified_times.insert(hltas.clone(), modified) == Some(modified) {
+                continue;
+            }
+
+            if let Some(snapshot) = snapshot(project_dir, &hltas)? {
+                info!("Saved snapshot {}", snapshot.display());
+            }
+            prune(project_dir, &hltas, cfg.history_limit, cfg.history_days)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Snapshots the scripts that changed since the last call, going by their modified time.
+///
+/// - For the loops that keep the scripts linked, the projects are found again each call so new ones are included.
+/// - Snapshots the given project, or all projects if there is none.
+/// - Errors are only logged, a failed snapshot shouldn't stop the scripts from being linked.
+pub fn snapshot_changed(
+    project_dir: Option<&Path>,
+    root_dir: &Path,
+    cfg: &Cfg,
+    modified_times: &mut HashMap<PathBuf, SystemTime>,
+) {
+    if cfg.history_limit == 0 {
+        return;
+    }
+
+    if let Err(err) = snapshot_changed_projects(project_dir, root_dir, cfg, modified_times) {
+        warn!("Failed to snapshot scripts: {err:#}");
+    }
+}
+
+/// How long ago a time was, such as `5m ago`.
+pub fn format_age(time: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or_default()
+        .as_secs();
+
+    match secs {
+        0..60 => format!("{secs}s ago"),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86_400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
@@ -2,8 +2,15 @@ pub mod cfg;
 pub mod cli;
 pub mod commands;
 pub mod files;
+pub mod game_build;
 pub(crate) mod helper;
+pub mod history;
+pub mod link_registry;
+pub mod lint;
+pub(crate) mod managed_block;
 pub mod project_toml;
+pub mod script;
+pub mod stats;
 
 pub const NAME: &str = env!("CARGO_PKG_NAME");
 pub const DEFAULT_GAME: &str = "valve";
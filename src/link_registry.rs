@@ -0,0 +1,86 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::helper;
+
+pub const FILE_NAME: &str = "hltas_links.toml";
+
+/// Keeps track of the links in the game directories created by the manager.
+///
+/// - Paths are relative to the root directory.
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct LinkRegistry {
+    pub links: Vec<Link>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub source: PathBuf,
+    pub link: PathBuf,
+    /// The file it was last linked as, see [`helper::file_id`], older registries don't have it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<String>,
+}
+
+impl Link {
+    /// If the file at the link is still the file it was last linked as.
+    ///
+    /// - Edits through the hard link keep the file, so an edited script that was renamed since is still ours.
+    /// - A different file could have been put there by the user, so it isn't ours to remove.
+    pub fn is_owned<P>(&self, root_dir: P) -> Result<bool>
+    where
+        P: AsRef<Path>,
+    {
+        let Some(file_id) = &self.file_id else {
+            return Ok(false);
+        };
+        let link = root_dir.as_ref().join(&self.link);
+
+        Ok(helper::file_id(link)? == *file_id)
+    }
+}
+
+impl LinkRegistry {
+    /// Loads the registry, or an empty one if it doesn't exist yet.
+    pub fn load_from_path<P>(path: P) -> Result<LinkRegistry>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        if !path.is_file() {
+            return Ok(LinkRegistry::default());
+        }
+
+        let registry = fs::read_to_string(path).context("Failed to read link registry")?;
+        let registry: LinkRegistry =
+            toml::from_str(&registry).context("Failed to parse link registry")?;
+
+        Ok(registry)
+    }
+
+    pub fn save_to_path<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let contents = toml::to_string(self)?;
+        fs::write(path, contents).context("Could not write to link registry")?;
+
+        Ok(())
+    }
+
+    /// Records a link, replacing any previous record of the same link.
+    pub fn insert(&mut self, source: PathBuf, link: PathBuf, file_id: String) {
+        self.links.retain(|l| l.link != link);
+        self.links.push(Link {
+            source,
+            link,
+            file_id: Some(file_id),
+        });
+    }
+}
@@ -0,0 +1,204 @@
+//! Finds errors and suspicious content in scripts.
+
+use std::fmt::{self, Display, Formatter};
+
+use hltas::{
+    types::{AutoMovement, FrameBulk, LeaveGroundActionType, Line},
+    HLTAS,
+};
+
+use crate::script;
+
+/// Console commands that end a script or hand over to the next one.
+pub const STOPPER_COMMANDS: &[&str] = &["stop", "pause", "bxt_tas_loadscript", "load", "quit"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem at a 1 based line and column.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Diagnostic {
+    fn warning(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+/// Line and column of a byte offset, both 1 based.
+fn position(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+
+    (line, column)
+}
+
+/// Lints a script, a script that fails to parse only has the parse error.
+pub fn lint(contents: &str) -> Vec<Diagnostic> {
+    if let Err(err) = HLTAS::from_str(contents) {
+        // the error input is the rest of the contents from where it failed
+        let offset = contents.len() - err.input.len();
+        let (line, column) = position(contents, offset);
+        let message = err
+            .to_string()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        return vec![Diagnostic {
+            line,
+            column,
+            severity: Severity::Error,
+            message,
+        }];
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut lines = contents.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    // properties until the frames line, the version line was checked by the parser
+    lines.find(|(_, line)| !line.trim().is_empty());
+    for (line_number, line) in lines.by_ref() {
+        let trimmed = line.trim();
+        if trimmed == "frames" {
+            break;
+        }
+
+        let name = trimmed.split_whitespace().next().unwrap_or_default();
+        if !name.is_empty() && !script::KNOWN_PROPERTIES.contains(&name) {
+            diagnostics.push(Diagnostic::warning(
+                line_number,
+                line.len() - line.trim_start().len() + 1,
+                format!("unknown property '{name}' is ignored"),
+            ));
+        }
+    }
+
+    let mut last_frame_bulk = None;
+    let mut ends_with_save = false;
+
+    for (line_number, line) in lines {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+        let Ok((_, parsed)) = hltas::read::line(trimmed) else {
+            continue;
+        };
+
+        match &parsed {
+            Line::FrameBulk(frame_bulk) => {
+                diagnostics.extend(frame_bulk_warnings(trimmed, frame_bulk).into_iter().map(
+                    |(column, message)| Diagnostic::warning(line_number, indent + column, message),
+                ));
+
+                last_frame_bulk = Some((line_number, frame_bulk.clone()));
+                ends_with_save = false;
+            }
+            Line::Save(_) => ends_with_save = true,
+            _ => {}
+        }
+    }
+
+    match last_frame_bulk {
+        Some((line_number, frame_bulk)) => {
+            let is_stopper = script::console_commands(&frame_bulk).iter().any(|command| {
+                let name = command.split_whitespace().next().unwrap_or_default();
+                STOPPER_COMMANDS.contains(&name)
+            });
+
+            if !is_stopper && !ends_with_save {
+                diagnostics.push(Diagnostic::warning(
+                    line_number,
+                    1,
+                    format!(
+                        "the script doesn't end with a stopper, the last frame bulk should run one of {}",
+                        STOPPER_COMMANDS.join(", ")
+                    ),
+                ));
+            }
+        }
+        None => diagnostics.push(Diagnostic::warning(1, 1, "the script has no frame bulks")),
+    }
+
+    diagnostics
+}
+
+/// Warnings of a frame bulk with the 1 based column in the line.
+fn frame_bulk_warnings(line: &str, frame_bulk: &FrameBulk) -> Vec<(usize, String)> {
+    let mut warnings = Vec::new();
+
+    // the parser reads 0 frames as 1 for backwards compatibility
+    let frames_column = line
+        .match_indices('|')
+        .nth(5)
+        .map(|(i, _)| i + 2)
+        .unwrap_or(1);
+    let frames = line.split('|').nth(6).unwrap_or_default();
+    if frames.trim() == "0" {
+        warnings.push((
+            frames_column,
+            "frame bulk has 0 frames, it runs for 1".to_string(),
+        ));
+    }
+
+    let auto_actions = &frame_bulk.auto_actions;
+    let movement_keys = &frame_bulk.movement_keys;
+    let action_keys = &frame_bulk.action_keys;
+    let leave_ground_action = auto_actions.leave_ground_action.map(|action| action.type_);
+
+    // up and down still work while strafing, they are used on ladders and in water
+    if matches!(auto_actions.movement, Some(AutoMovement::Strafe(_)))
+        && (movement_keys.forward
+            || movement_keys.left
+            || movement_keys.right
+            || movement_keys.back)
+    {
+        warnings.push((
+            1,
+            "forward, left, right and back keys are ignored while strafing".to_string(),
+        ));
+    }
+    if leave_ground_action == Some(LeaveGroundActionType::Jump) && action_keys.jump {
+        warnings.push((1, "both autojump and the jump key are set".to_string()));
+    }
+    if matches!(
+        leave_ground_action,
+        Some(LeaveGroundActionType::DuckTap { .. })
+    ) {
+        if action_keys.duck {
+            warnings.push((1, "both ducktap and the duck key are set".to_string()));
+        }
+        if auto_actions.jump_bug.is_some() {
+            warnings.push((1, "both ducktap and jumpbug are set".to_string()));
+        }
+    }
+
+    warnings
+}
+
+/// If any of the diagnostics is an error.
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
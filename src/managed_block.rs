@@ -0,0 +1,151 @@
+//! A block of lines in a user editable file that the manager owns.
+//!
+//! The block is surrounded by marker comments so it can be found, updated and removed without
+//! touching the rest of the file.
+
+use std::ops::Range;
+
+fn begin_marker(comment: &str) -> String {
+    format!(
+        "{comment} BEGIN {} managed block, changes will be overwritten",
+        crate::NAME
+    )
+}
+
+fn end_marker(comment: &str) -> String {
+    format!("{comment} END {} managed block", crate::NAME)
+}
+
+/// Byte range of the whole block including the markers and the line ending after the end marker.
+fn block_range(contents: &str, comment: &str) -> Option<Range<usize>> {
+    let begin_marker = begin_marker(comment);
+    let end_marker = end_marker(comment);
+
+    let mut start = None;
+    let mut offset = 0;
+
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+
+        if start.is_none() && trimmed == begin_marker {
+            start = Some(offset);
+        } else if let Some(start) = start {
+            if trimmed == end_marker {
+                return Some(start..offset + line.len());
+            }
+        }
+
+        offset += line.len();
+    }
+
+    None
+}
+
+/// Returns the lines inside the block, if the block exists.
+///
+/// - The lines keep the line endings of the file, compare them with [`str::lines`].
+pub fn get<'a>(contents: &'a str, comment: &str) -> Option<&'a str> {
+    let range = block_range(contents, comment)?;
+    let block = &contents[range];
+
+    let body_start = block.find('\n')? + 1;
+    let body_end = block.trim_end().rfind('\n').map_or(body_start, |i| i + 1);
+
+    Some(&block[body_start..body_end.max(body_start)])
+}
+
+/// Inserts the block at the end, or replaces it in place if it exists.
+///
+/// - The block uses CRLF line endings if the file already does.
+pub fn upsert(contents: &str, comment: &str, body: &str) -> String {
+    let mut block = begin_marker(comment);
+    block.push('\n');
+    block.push_str(body);
+    if !body.is_empty() && !body.ends_with('\n') {
+        block.push('\n');
+    }
+    block.push_str(&end_marker(comment));
+    block.push('\n');
+
+    if contents.contains("\r\n") {
+        block = block.replace("\r\n", "\n").replace('\n', "\r\n");
+    }
+
+    match block_range(contents, comment) {
+        Some(range) => {
+            let mut contents = contents.to_string();
+            contents.replace_range(range, &block);
+            contents
+        }
+        None => {
+            let mut contents = contents.to_string();
+            if !contents.is_empty() && !contents.ends_with('\n') {
+                contents.push_str(if contents.contains("\r\n") {
+                    "\r\n"
+                } else {
+                    "\n"
+                });
+            }
+            contents.push_str(&block);
+            contents
+        }
+    }
+}
+
+/// Removes the block if it exists.
+pub fn remove(contents: &str, comment: &str) -> String {
+    match block_range(contents, comment) {
+        Some(range) => {
+            let mut contents = contents.to_string();
+            contents.replace_range(range, "");
+            contents
+        }
+        None => contents.to_string(),
+    }
+}
+
+/// Edits the text before and after the block separately, the block stays where it is.
+pub fn edit_outside<F>(contents: &str, comment: &str, edit: F) -> String
+where
+    F: Fn(&str) -> String,
+{
+    match block_range(contents, comment) {
+        Some(range) => format!(
+            "{}{}{}",
+            edit(&contents[..range.start]),
+            &contents[range.clone()],
+            edit(&contents[range.end..])
+        ),
+        None => edit(contents),
+    }
+}
+
+/// Removes every copy of a run of lines that older versions wrote without a block.
+///
+/// - Returns the text and how many copies were removed.
+/// - Lines have to match exactly apart from their line endings, so lines the user wrote are kept.
+pub fn strip_runs(text: &str, run: &[&str]) -> (String, usize) {
+    let lines = text.split_inclusive('\n').collect::<Vec<_>>();
+    let is_run = |lines: &[&str]| {
+        lines.len() >= run.len()
+            && lines
+                .iter()
+                .zip(run)
+                .all(|(line, run_line)| line.trim_end_matches(['\r', '\n']) == *run_line)
+    };
+
+    let mut stripped = String::with_capacity(text.len());
+    let mut removed = 0;
+    let mut i = 0;
+    while i < lines.len() {
+        if !run.is_empty() && is_run(&lines[i..]) {
+            removed += 1;
+            i += run.len();
+        } else {
+            stripped.push_str(lines[i]);
+            i += 1;
+        }
+    }
+
+    (stripped, removed)
+}
@@ -1,15 +1,40 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
 
-use crate::DEFAULT_GAME;
+use crate::{game_build::GameBuild, helper, DEFAULT_GAME};
 
 pub const FILE_NAME: &str = "project.toml";
 
 #[derive(Serialize, Deserialize)]
 pub struct ProjectToml {
     pub game: String,
+    /// Category of the run, such as `any%`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Maps of the run in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maps: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route_notes: Option<String>,
+    /// Target time of the run in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_time: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    #[serde(default)]
+    pub scripts: Scripts,
+    /// Segments of the run in order, each starting from the save the previous one made.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<Segment>,
+    /// The engine and Bunnymod XT build the run is verified on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified_build: Option<GameBuild>,
     // TODO command line options for project
 }
 
@@ -17,10 +42,124 @@ impl Default for ProjectToml {
     fn default() -> Self {
         Self {
             game: DEFAULT_GAME.to_string(),
+            category: None,
+            maps: Vec::new(),
+            route_notes: None,
+            target_time: None,
+            authors: Vec::new(),
+            scripts: Scripts::default(),
+            segments: Vec::new(),
+            verified_build: None,
         }
     }
 }
 
+/// A part of the run played by a single script.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Segment {
+    pub name: String,
+    /// Path of the script relative to the project dir.
+    pub script: PathBuf,
+    /// The save the segment loads, the first segment usually starts from a map instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_save: Option<String>,
+    /// The save the segment makes for the next one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_save: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map: Option<String>,
+    /// Target time of the segment in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_time: Option<f64>,
+}
+
+/// Which files in the project are scripts.
+///
+/// - Globs are matched against the path relative to the project dir, using `/` as the separator.
+#[derive(Serialize, Deserialize)]
+pub struct Scripts {
+    #[serde(default = "default_include")]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+fn default_include() -> Vec<String> {
+    vec!["**/*.hltas".to_string()]
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        Self {
+            include: default_include(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl Scripts {
+    fn glob_set(globs: &[String]) -> Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+
+        for glob in globs {
+            builder.add(
+                GlobBuilder::new(glob)
+                    .literal_separator(true)
+                    .build()
+                    .with_context(|| format!("Invalid glob '{glob}' in project config"))?,
+            );
+        }
+
+        builder.build().context("Failed to build globs")
+    }
+
+    /// Walks the project dir for scripts, skipping hidden directories such as `.git`.
+    pub fn find<P>(&self, project_dir: P) -> Result<Vec<PathBuf>>
+    where
+        P: AsRef<Path>,
+    {
+        let project_dir = project_dir.as_ref();
+        let include = Self::glob_set(&self.include)?;
+        let exclude = Self::glob_set(&self.exclude)?;
+
+        let mut hltases = Vec::new();
+        let mut dirs = vec![project_dir.to_path_buf()];
+
+        while let Some(dir) = dirs.pop() {
+            for entry in dir
+                .read_dir()
+                .with_context(|| format!("Failed to read directory {}", dir.display()))?
+            {
+                let entry = entry.context("Failed to read dir")?;
+                let path = entry.path();
+
+                if path.is_dir() {
+                    if !entry.file_name().to_string_lossy().starts_with('.') {
+                        dirs.push(path);
+                    }
+                    continue;
+                }
+
+                let relative = path
+                    .strip_prefix(project_dir)
+                    .context("Failed to get path relative to project")?
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                if include.is_match(&relative) && !exclude.is_match(&relative) {
+                    hltases.push(path);
+                }
+            }
+        }
+
+        hltases.sort();
+
+        Ok(hltases)
+    }
+}
+
 impl ProjectToml {
     pub fn load_from_path<P>(path: P) -> Result<ProjectToml>
     where
@@ -43,6 +182,77 @@ impl ProjectToml {
         Ok(())
     }
 
+    /// Describes the metadata of the project that is set, one line each.
+    pub fn metadata(&self) -> Vec<String> {
+        let mut metadata = vec![format!("game: {}", self.game)];
+
+        if let Some(category) = &self.category {
+            metadata.push(format!("category: {category}"));
+        }
+        if !self.maps.is_empty() {
+            metadata.push(format!("maps: {}", self.maps.join(", ")));
+        }
+        if let Some(target_time) = self.target_time {
+            metadata.push(format!("target time: {}", helper::format_time(target_time)));
+        }
+        if !self.authors.is_empty() {
+            metadata.push(format!("authors: {}", self.authors.join(", ")));
+        }
+        if let Some(route_notes) = &self.route_notes {
+            metadata.push(format!("route notes: {}", route_notes.trim()));
+        }
+        if let Some(verified_build) = &self.verified_build {
+            let short_hash = |hash: &Option<String>| match hash {
+                Some(hash) => hash.chars().take(8).collect(),
+                None => "unknown".to_string(),
+            };
+
+            metadata.push(format!(
+                "verified build: engine {}, Bunnymod XT {}",
+                short_hash(&verified_build.engine_sha256),
+                short_hash(&verified_build.bxt_sha256)
+            ));
+        }
+
+        metadata
+    }
+
+    /// Finds a segment by name, or by its 1 based position in the run.
+    pub fn segment(&self, segment: &str) -> Result<(usize, &Segment)> {
+        let by_name = self.segments.iter().position(|s| s.name == segment);
+        let by_number = || {
+            segment
+                .parse::<usize>()
+                .ok()
+                .filter(|n| (1..=self.segments.len()).contains(n))
+                .map(|n| n - 1)
+        };
+
+        match by_name.or_else(by_number) {
+            Some(i) => Ok((i, &self.segments[i])),
+            None if self.segments.is_empty() => {
+                bail!("The project has no segments\nHelp: Add [[segments]] to project.toml")
+            }
+            None => bail!(
+                "Segment '{segment}' not found, the segments are: {}",
+                self.segments
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| format!("{} {}", i + 1, s.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Lists the parts of `build` that differ from the verified build, if there is one.
+    pub fn verified_build_differences(&self, build: &GameBuild) -> Vec<&'static str> {
+        match &self.verified_build {
+            Some(verified_build) => build.differences(verified_build),
+            None => Vec::new(),
+        }
+    }
+
     pub fn save_default_to_path<P>(path: P) -> Result<()>
     where
         P: AsRef<Path>,
@@ -0,0 +1,1143 @@
+//! Reading and writing `.hltas` scripts.
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use hltas::{
+    types::{
+        AutoMovement, FrameBulk, LeaveGroundActionType, Line, Properties, Seeds, StrafeDir,
+        StrafeType,
+    },
+    HLTAS,
+};
+
+/// Names of the fields of a frame bulk line, in the order they are written.
+pub const FRAME_BULK_FIELDS: [&str; 8] = [
+    "auto actions",
+    "movement keys",
+    "action keys",
+    "frametime",
+    "yaw",
+    "pitch",
+    "frames",
+    "command",
+];
+
+/// Properties the parser reads, others are silently dropped.
+pub const KNOWN_PROPERTIES: &[&str] = &[
+    "demo",
+    "save",
+    "frametime0ms",
+    "seed",
+    "hlstrafe_version",
+    "load_command",
+];
+
+/// Parses a script, the error is formatted with the line it happened on.
+pub fn parse(contents: &str) -> Result<HLTAS> {
+    HLTAS::from_str(contents).map_err(|err| anyhow!("{err}"))
+}
+
+pub fn load<P>(path: P) -> Result<HLTAS>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    parse(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn to_string(hltas: &HLTAS) -> Result<String> {
+    let mut buf = Vec::new();
+    hltas
+        .to_writer(&mut buf)
+        .map_err(|err| anyhow!("Failed to write script: {err:?}"))?;
+
+    Ok(String::from_utf8_lossy(&buf).to_string())
+}
+
+/// A line after the `frames` line with its text as written, see [`line_texts`].
+#[derive(Debug, Clone)]
+pub struct TextLine {
+    pub line: Line,
+    pub text: String,
+}
+
+impl TextLine {
+    /// A line the manager adds, written by the writer.
+    pub fn new(line: Line) -> Self {
+        let text = line_to_string(&line);
+        Self { line, text }
+    }
+
+    /// Sets the console command of a frame bulk, keeping the text of its other fields.
+    pub fn set_console_command(&mut self, console_command: Option<String>) {
+        let Line::FrameBulk(frame_bulk) = &mut self.line else {
+            return;
+        };
+
+        let mut fields = self
+            .text
+            .splitn(FRAME_BULK_FIELDS.len(), '|')
+            .take(FRAME_BULK_FIELDS.len() - 1)
+            .collect::<Vec<_>>()
+            .join("|");
+        if let Some(console_command) = &console_command {
+            fields.push('|');
+            fields.push_str(console_command);
+        }
+
+        self.text = fields;
+        frame_bulk.console_command = console_command;
+    }
+}
+
+/// Loads a script with the text of each of its lines.
+///
+/// - The parser reads numbers as `f32`, writing the lines from their text keeps the numbers as Bunnymod XT reads them.
+pub fn load_text_lines<P>(path: P) -> Result<(Properties, Vec<TextLine>)>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let hltas = parse(&contents).with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let texts = line_texts(&contents);
+    if texts.len() != hltas.lines.len() {
+        bail!("Failed to match the lines of {}", path.display());
+    }
+    let lines = hltas
+        .lines
+        .into_iter()
+        .zip(texts)
+        .map(|(line, text)| TextLine {
+            line,
+            text: text.to_string(),
+        })
+        .collect();
+
+    Ok((hltas.properties, lines))
+}
+
+/// The properties of a script and the `frames` line.
+fn header(properties: &Properties) -> Result<String> {
+    let mut header = Vec::new();
+    hltas::write::gen_hltas(
+        &mut header,
+        &HLTAS {
+            properties: properties.clone(),
+            lines: Vec::new(),
+        },
+    )
+    .map_err(|err| anyhow!("Failed to write properties: {err:?}"))?;
+
+    Ok(String::from_utf8_lossy(&header).to_string())
+}
+
+/// Writes a script from its properties and the text of its lines.
+///
+/// - The properties hold no floats, so the writer keeps them as they are.
+/// - Fails if the text of the lines doesn't read as the lines.
+pub fn text_lines_to_string(properties: &Properties, lines: &[TextLine]) -> Result<String> {
+    let mut contents = header(properties)?;
+    for line in lines {
+        contents.push_str(&line.text);
+        contents.push('\n');
+    }
+
+    let hltas = parse(&contents).context("The written script doesn't parse")?;
+    if hltas.lines.len() != lines.len() || hltas.lines.iter().zip(lines).any(|(a, b)| *a != b.line)
+    {
+        bail!("The written script doesn't read as its lines");
+    }
+
+    Ok(contents)
+}
+
+pub fn save_text_lines<P>(path: P, properties: &Properties, lines: &[TextLine]) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+
+    fs::write(path, text_lines_to_string(properties, lines)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Reads the version from the `version` line at the start of the script.
+pub fn version(contents: &str) -> Option<u32> {
+    contents
+        .lines()
+        .find(|line| !line.trim().is_empty())?
+        .trim()
+        .strip_prefix("version")?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// The lines after the `frames` line as written without their indentation, one for each of [`HLTAS::lines`].
+pub fn line_texts(contents: &str) -> Vec<&str> {
+    contents
+        .lines()
+        .skip_while(|line| line.trim() != "frames")
+        .skip(1)
+        .map(str::trim_start)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Sets a property in the header of a script, or removes it with `None`, keeping the rest as written.
+pub fn set_property(contents: &str, name: &str, value: Option<&str>) -> Result<String> {
+    let mut lines = contents.lines().map(str::to_string).collect::<Vec<_>>();
+
+    // properties are between the version line and the frames line
+    let start = lines
+        .iter()
+        .position(|line| !line.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    let Some(frames) = lines[start..]
+        .iter()
+        .position(|line| line.trim() == "frames")
+        .map(|i| start + i)
+    else {
+        bail!("The script has no frames line");
+    };
+
+    let existing = (start..frames).find(|i| lines[*i].split_whitespace().next() == Some(name));
+
+    match (existing, value) {
+        (Some(i), Some(value)) => lines[i] = format!("{name} {value}"),
+        (Some(i), None) => {
+            lines.remove(i);
+        }
+        (None, Some(value)) => lines.insert(frames, format!("{name} {value}")),
+        (None, None) => {}
+    }
+
+    let mut modified = lines.join("\n");
+    if contents.ends_with('\n') {
+        modified.push('\n');
+    }
+
+    parse(&modified).context("The script doesn't parse after setting the property")?;
+    Ok(modified)
+}
+
+/// Replaces a line after the `frames` line, counted as in [`line_texts`], keeping the rest as written.
+pub fn set_line(contents: &str, index: usize, text: &str) -> Result<String> {
+    let mut modified = String::with_capacity(contents.len());
+    let mut in_frames = false;
+    let mut i = 0;
+
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if in_frames && !trimmed.is_empty() {
+            if i == index {
+                // keep the indentation and line ending as written
+                let start = line.len() - line.trim_start().len();
+                let end = line.trim_end_matches(['\r', '\n']).len();
+                modified.push_str(&line[..start]);
+                modified.push_str(text);
+                modified.push_str(&line[end..]);
+                i += 1;
+                continue;
+            }
+            i += 1;
+        }
+
+        in_frames |= trimmed == "frames";
+        modified.push_str(line);
+    }
+
+    if index >= i {
+        bail!("The script has no line {index}, it has {i}");
+    }
+
+    parse(&modified).context("The script doesn't parse after setting the line")?;
+    Ok(modified)
+}
+
+/// Reads seeds written as `<shared> <non-shared>`, or only the shared seed keeping `non_shared`.
+pub fn parse_seeds(seeds: &str, non_shared: i64) -> Result<Seeds> {
+    let parts = seeds.split_whitespace().collect::<Vec<_>>();
+    let invalid = || format!("Invalid seed '{seeds}'\nHelp: Write it as '<shared> <non-shared>'");
+
+    match parts.as_slice() {
+        [shared] => Ok(Seeds {
+            shared: shared.parse().with_context(invalid)?,
+            non_shared,
+        }),
+        [shared, non_shared] => Ok(Seeds {
+            shared: shared.parse().with_context(invalid)?,
+            non_shared: non_shared.parse().with_context(invalid)?,
+        }),
+        _ => bail!(invalid()),
+    }
+}
+
+pub fn seeds_to_string(seeds: &Seeds) -> String {
+    format!("{} {}", seeds.shared, seeds.non_shared)
+}
+
+/// Lines that set state for the rest of the script, which a loaded script starts without.
+pub fn is_persistent_state(line: &Line) -> bool {
+    matches!(
+        line,
+        Line::Buttons(_)
+            | Line::LGAGSTMinSpeed(_)
+            | Line::VectorialStrafing(_)
+            | Line::VectorialStrafingConstraints(_)
+    )
+}
+
+/// The last line of each kind of persistent state before `end`.
+pub fn state_at(lines: &[TextLine], end: usize) -> Vec<TextLine> {
+    let mut state: Vec<TextLine> = Vec::new();
+
+    for line in lines[..end]
+        .iter()
+        .filter(|line| is_persistent_state(&line.line))
+    {
+        state.retain(|l| std::mem::discriminant(&l.line) != std::mem::discriminant(&line.line));
+        state.push(line.clone());
+    }
+
+    state
+}
+
+/// Names of the properties of a script with their values as written in the script.
+pub fn properties(properties: &Properties) -> [(&'static str, Option<String>); 6] {
+    [
+        ("demo", properties.demo.clone()),
+        ("save", properties.save.clone()),
+        ("frametime0ms", properties.frametime_0ms.clone()),
+        ("seed", properties.seeds.as_ref().map(seeds_to_string)),
+        (
+            "hlstrafe_version",
+            properties
+                .hlstrafe_version
+                .map(|version| version.to_string()),
+        ),
+        ("load_command", properties.load_command.clone()),
+    ]
+}
+
+/// Frame time of a frame bulk in seconds, `0` if it can't be read.
+pub fn frame_time(frame_bulk: &FrameBulk) -> f64 {
+    frame_bulk.frame_time.trim().parse().unwrap_or(0.)
+}
+
+pub fn total_frames(hltas: &HLTAS) -> u64 {
+    hltas
+        .frame_bulks()
+        .map(|frame_bulk| u64::from(frame_bulk.frame_count.get()))
+        .sum()
+}
+
+/// Game time of the script in seconds from the frame times of its frame bulks.
+pub fn estimated_time(hltas: &HLTAS) -> f64 {
+    hltas
+        .frame_bulks()
+        .map(|frame_bulk| f64::from(frame_bulk.frame_count.get()) * frame_time(frame_bulk))
+        .sum()
+}
+
+/// Names of the strafe types, in the order of their number in the auto actions field.
+pub const STRAFE_TYPES: [&str; 6] = [
+    "max-accel",
+    "max-angle",
+    "max-deccel",
+    "const-speed",
+    "const-yawspeed",
+    "max-accel-yaw-offset",
+];
+
+/// Names of the automatic actions a frame bulk can do.
+pub const AUTO_ACTIONS: [&str; 6] = ["autojump", "ducktap", "jumpbug", "dbc", "dbg", "dwj"];
+
+/// Names of the keys a frame bulk can hold.
+pub const KEYS: [&str; 12] = [
+    "forward", "left", "right", "back", "up", "down", "jump", "duck", "use", "attack1", "attack2",
+    "reload",
+];
+
+pub fn strafe_type_name(strafe_type: StrafeType) -> &'static str {
+    let i = match strafe_type {
+        StrafeType::MaxAccel => 0,
+        StrafeType::MaxAngle => 1,
+        StrafeType::MaxDeccel => 2,
+        StrafeType::ConstSpeed => 3,
+        StrafeType::ConstYawspeed(_) => 4,
+        StrafeType::MaxAccelYawOffset { .. } => 5,
+    };
+
+    STRAFE_TYPES[i]
+}
+
+/// The strafe type of a frame bulk, `None` if it doesn't strafe.
+pub fn strafe_type(frame_bulk: &FrameBulk) -> Option<&'static str> {
+    match frame_bulk.auto_actions.movement {
+        Some(AutoMovement::Strafe(settings)) => Some(strafe_type_name(settings.type_)),
+        _ => None,
+    }
+}
+
+/// The yaw of a frame bulk, set directly or as the direction a strafe goes towards.
+pub fn yaw(frame_bulk: &FrameBulk) -> Option<f32> {
+    match frame_bulk.auto_actions.movement? {
+        AutoMovement::SetYaw(yaw) => Some(yaw),
+        AutoMovement::Strafe(settings) => match settings.dir {
+            StrafeDir::Yaw(yaw) | StrafeDir::Line { yaw } => Some(yaw),
+            _ => None,
+        },
+    }
+}
+
+/// The automatic actions of a frame bulk, named as in [`AUTO_ACTIONS`].
+pub fn auto_actions(frame_bulk: &FrameBulk) -> Vec<&'static str> {
+    let auto_actions = &frame_bulk.auto_actions;
+    let mut names = Vec::new();
+
+    match auto_actions.leave_ground_action.map(|action| action.type_) {
+        Some(LeaveGroundActionType::Jump) => names.push("autojump"),
+        Some(LeaveGroundActionType::DuckTap { .. }) => names.push("ducktap"),
+        None => {}
+    }
+    if auto_actions.jump_bug.is_some() {
+        names.push("jumpbug");
+    }
+    if auto_actions.duck_before_collision.is_some() {
+        names.push("dbc");
+    }
+    if auto_actions.duck_before_ground.is_some() {
+        names.push("dbg");
+    }
+    if auto_actions.duck_when_jump.is_some() {
+        names.push("dwj");
+    }
+
+    names
+}
+
+/// The keys a frame bulk holds, named as in [`KEYS`].
+pub fn keys(frame_bulk: &FrameBulk) -> Vec<&'static str> {
+    let movement = &frame_bulk.movement_keys;
+    let action = &frame_bulk.action_keys;
+
+    [
+        movement.forward,
+        movement.left,
+        movement.right,
+        movement.back,
+        movement.up,
+        movement.down,
+        action.jump,
+        action.duck,
+        action.use_,
+        action.attack_1,
+        action.attack_2,
+        action.reload,
+    ]
+    .into_iter()
+    .zip(KEYS)
+    .filter(|(held, _)| *held)
+    .map(|(_, name)| name)
+    .collect()
+}
+
+/// Splits the console command of a frame bulk into the commands separated by `;`.
+pub fn console_commands(frame_bulk: &FrameBulk) -> Vec<&str> {
+    match &frame_bulk.console_command {
+        Some(console_command) => console_command
+            .split(';')
+            .map(str::trim)
+            .filter(|command| !command.is_empty())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Saves a script makes and loads, each in the order they first appear.
+#[derive(Debug, Default)]
+pub struct SaveUses {
+    pub made: Vec<String>,
+    pub loaded: Vec<String>,
+}
+
+/// Reads the save of a `save` or `load` console command, as `(is_save, name)`.
+pub fn save_command(command: &str) -> Option<(bool, String)> {
+    let mut args = command.split_whitespace();
+    let is_save = match args.next()? {
+        "save" => true,
+        "load" => false,
+        _ => return None,
+    };
+    let name = args.next()?.trim_matches('"');
+
+    if name.is_empty() {
+        return None;
+    }
+    Some((is_save, name.to_string()))
+}
+
+/// Finds the saves a script makes and loads through its properties, save lines and console commands.
+pub fn save_uses(hltas: &HLTAS) -> SaveUses {
+    let mut uses = SaveUses::default();
+    let mut add = |is_save: bool, name: String| {
+        let saves = if is_save {
+            &mut uses.made
+        } else {
+            &mut uses.loaded
+        };
+        if !saves.contains(&name) {
+            saves.push(name);
+        }
+    };
+
+    let properties = &hltas.properties;
+    for command in properties.load_command.iter().flat_map(|c| c.split(';')) {
+        if let Some((is_save, name)) = save_command(command) {
+            add(is_save, name);
+        }
+    }
+
+    for line in &hltas.lines {
+        match line {
+            // a save line saves and loads the save right away
+            Line::Save(name) => add(true, name.clone()),
+            Line::FrameBulk(frame_bulk) => {
+                for command in console_commands(frame_bulk) {
+                    if let Some((is_save, name)) = save_command(command) {
+                        add(is_save, name);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(save) = &properties.save {
+        add(true, save.clone());
+    }
+
+    uses
+}
+
+pub fn frame_bulk_to_string(frame_bulk: &FrameBulk) -> String {
+    let mut buf = Vec::new();
+    // writing to a vec can't fail
+    hltas::write::gen_frame_bulk(&mut buf, frame_bulk).unwrap();
+    String::from_utf8_lossy(&buf).to_string()
+}
+
+pub fn line_to_string(line: &Line) -> String {
+    let mut buf = Vec::new();
+    hltas::write::gen_line(&mut buf, line).unwrap();
+    String::from_utf8_lossy(&buf).to_string()
+}
+
+/// Splits a frame bulk into the fields named in [`FRAME_BULK_FIELDS`].
+pub fn frame_bulk_fields(frame_bulk: &FrameBulk) -> Vec<String> {
+    let line = frame_bulk_to_string(frame_bulk);
+    let mut fields = line
+        .splitn(FRAME_BULK_FIELDS.len(), '|')
+        .map(|field| field.to_string())
+        .collect::<Vec<_>>();
+
+    fields.resize(FRAME_BULK_FIELDS.len(), String::new());
+    fields
+}
+
+/// Writes a number in its shortest plain decimal form, such as `0.0010` or `1e-3` as `0.001`.
+///
+/// - Text that isn't a number, or has an exponent too large to write out, is kept as written.
+pub fn normalize_number(number: &str) -> String {
+    let number = number.trim();
+    let (negative, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, number.strip_prefix('+').unwrap_or(number)),
+    };
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => match exponent.parse::<i32>() {
+            Ok(exponent) if exponent.abs() <= 64 => (mantissa, exponent),
+            _ => return number.to_string(),
+        },
+        None => (unsigned, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if (int.is_empty() && frac.is_empty())
+        || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+    {
+        return number.to_string();
+    }
+
+    // move the decimal point by the exponent
+    let digits = format!("{int}{frac}");
+    let point = int.len() as i32 + exponent;
+    let (int, frac) = if point <= 0 {
+        (String::new(), "0".repeat(-point as usize) + &digits)
+    } else if point as usize >= digits.len() {
+        (
+            digits.clone() + &"0".repeat(point as usize - digits.len()),
+            String::new(),
+        )
+    } else {
+        let (int, frac) = digits.split_at(point as usize);
+        (int.to_string(), frac.to_string())
+    };
+
+    let int = match int.trim_start_matches('0') {
+        "" => "0",
+        int => int,
+    };
+    let mut normalized = match frac.trim_end_matches('0') {
+        "" => int.to_string(),
+        frac => format!("{int}.{frac}"),
+    };
+    if negative && normalized != "0" {
+        normalized.insert(0, '-');
+    }
+
+    normalized
+}
+
+/// Writes a comment so it starts with `// `.
+fn format_comment(comment: &str) -> String {
+    let comment = comment.trim_end();
+    if comment.is_empty() || comment.starts_with(char::is_whitespace) {
+        line_to_string(&Line::Comment(comment.to_string()))
+    } else {
+        line_to_string(&Line::Comment(format!(" {comment}")))
+    }
+}
+
+/// Writes a line other than a frame bulk or comment with single spaces and normalized numbers.
+///
+/// - The writer would round the numbers to `f32`, so the text of the line is kept.
+/// - Lines that read differently once rewritten, such as a save named `007`, are kept as written.
+fn format_line(text: &str, line: &Line) -> String {
+    let text = text.trim();
+    let formatted = normalize_numbers(text);
+
+    match hltas::read::line(&formatted) {
+        Ok((_, formatted_line)) if formatted_line == *line => formatted,
+        _ => text.to_string(),
+    }
+}
+
+/// Writes a frame bulk in the canonical layout, as the fields up to the frame count and the command.
+///
+/// - The auto actions, keys and frame count are written by the writer, they hold only integers.
+/// - The frametime, yaw and pitch fields keep their text with the numbers normalized, the writer would round them to `f32`.
+fn format_frame_bulk(text: &str, frame_bulk: &FrameBulk) -> FormattedLine {
+    let written = frame_bulk_to_string(&FrameBulk {
+        console_command: None,
+        ..frame_bulk.clone()
+    });
+    let text_fields = text
+        .splitn(FRAME_BULK_FIELDS.len(), '|')
+        .collect::<Vec<_>>();
+
+    let fields = written
+        .split('|')
+        .enumerate()
+        .map(|(i, field)| match i {
+            3..=5 => normalize_numbers(text_fields[i]),
+            _ => field.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("|");
+    let command = text_fields
+        .get(7)
+        .map(|command| command.trim())
+        .filter(|command| !command.is_empty())
+        .map(str::to_string);
+
+    FormattedLine::FrameBulk { fields, command }
+}
+
+/// If two numbers written as text have the same exact decimal value.
+fn same_number(a: &str, b: &str) -> bool {
+    normalize_number(a) == normalize_number(b)
+}
+
+/// Normalizes every number in the text of a field, see [`normalize_number`].
+fn normalize_numbers(text: &str) -> String {
+    text.split_whitespace()
+        .map(normalize_number)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The numbers of the lines after the `frames` line as their exact decimal value, comments aside.
+///
+/// - The parser reads numbers as `f32` but Bunnymod XT reads them as `f64`, so they are compared as written.
+/// - Frame counts and the counts in the auto actions are left out, they are integers the parser reads exactly.
+fn frame_numbers(contents: &str) -> Vec<String> {
+    let mut numbers = Vec::new();
+
+    for line in line_texts(contents) {
+        let text = match hltas::read::line(line) {
+            Ok((_, Line::Comment(_))) => continue,
+            // the frametime, yaw and pitch fields, not the command
+            Ok((_, Line::FrameBulk(_))) => line
+                .splitn(FRAME_BULK_FIELDS.len(), '|')
+                .skip(3)
+                .take(3)
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => line.to_string(),
+        };
+
+        numbers.extend(
+            text.split_whitespace()
+                .filter(|token| token.parse::<f64>().is_ok())
+                .map(normalize_number),
+        );
+    }
+
+    numbers
+}
+
+/// The console command of a frame bulk as the console runs it, without the whitespace around it.
+fn run_command(frame_bulk: &FrameBulk) -> &str {
+    frame_bulk
+        .console_command
+        .as_deref()
+        .unwrap_or_default()
+        .trim()
+}
+
+/// If two scripts make the game do the same thing.
+///
+/// - The parsed scripts are compared field by field, comments aside.
+/// - Frametimes are compared by value and console commands without the whitespace around them.
+/// - Other numbers are compared as the parser reads them, as `f32`, see [`format`] for comparing their text.
+pub fn same_behavior(a: &HLTAS, b: &HLTAS) -> bool {
+    let same_frametime_0ms = match (&a.properties.frametime_0ms, &b.properties.frametime_0ms) {
+        (Some(a), Some(b)) => same_number(a, b),
+        (a, b) => a == b,
+    };
+    let properties = |hltas: &HLTAS| Properties {
+        frametime_0ms: None,
+        ..hltas.properties.clone()
+    };
+    if !same_frametime_0ms || properties(a) != properties(b) {
+        return false;
+    }
+
+    let lines = |hltas: &HLTAS| {
+        hltas
+            .lines
+            .iter()
+            .filter(|line| !matches!(line, Line::Comment(_)))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let (a, b) = (lines(a), lines(b));
+
+    a.len() == b.len()
+        && a.into_iter().zip(b).all(|lines| match lines {
+            (Line::FrameBulk(a), Line::FrameBulk(b)) => {
+                let rest = |frame_bulk: &FrameBulk| FrameBulk {
+                    frame_time: String::new(),
+                    console_command: None,
+                    ..frame_bulk.clone()
+                };
+
+                same_number(&a.frame_time, &b.frame_time)
+                    && run_command(&a) == run_command(&b)
+                    && rest(&a) == rest(&b)
+            }
+            (a, b) => a == b,
+        })
+}
+
+/// Something `upgrade` rewrote, at a 1 based line of the original script.
+#[derive(Debug, Clone)]
+pub struct UpgradeNote {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Rewrites a script from the older forms the parser still accepts to the current format.
+///
+/// - Version 1 is the only version of the format, older forms are ones that version still tolerates.
+/// - Frame counts written as `0` or `-` are run as 1 frame and are written as `1`.
+/// - Extra characters after the auto actions field, which older versions ignored, are removed.
+/// - Properties the parser doesn't read are kept, a newer Bunnymod XT may read them.
+/// - Only the outdated fields are rewritten, the layout is left to [`format`], so a script without notes is unchanged.
+/// - Fails if the upgraded script would behave differently.
+pub fn upgrade(contents: &str) -> Result<(String, Vec<UpgradeNote>)> {
+    // versions the parser doesn't support fail here
+    let hltas = parse(contents)?;
+    let mut notes = Vec::new();
+    let mut upgraded = String::with_capacity(contents.len());
+    let mut in_frames = false;
+
+    for (i, line) in contents.split_inclusive('\n').enumerate() {
+        let text = line.trim();
+        let frame_bulk = match hltas::read::line(text) {
+            Ok((_, Line::FrameBulk(frame_bulk))) if in_frames => frame_bulk,
+            _ => {
+                // the frame bulks start after the properties
+                in_frames |= text == "frames";
+                upgraded.push_str(line);
+                continue;
+            }
+        };
+
+        let written = frame_bulk_to_string(&FrameBulk {
+            console_command: None,
+            ..frame_bulk
+        });
+        let written = written.split('|').collect::<Vec<_>>();
+        let mut fields = text
+            .splitn(FRAME_BULK_FIELDS.len(), '|')
+            .collect::<Vec<_>>();
+
+        if fields[0] != written[0] {
+            let message = match fields[0].strip_prefix(written[0]) {
+                Some(extra) => format!("removed '{extra}' after the auto actions, it is ignored"),
+                None => format!("wrote auto actions '{}' as '{}'", fields[0], written[0]),
+            };
+            notes.push(UpgradeNote {
+                line: i + 1,
+                message,
+            });
+            fields[0] = written[0];
+        }
+        if fields[6] != written[6] {
+            notes.push(UpgradeNote {
+                line: i + 1,
+                message: format!(
+                    "wrote frame count '{}' as {}, which it runs as",
+                    fields[6], written[6]
+                ),
+            });
+            fields[6] = written[6];
+        }
+
+        // keep the indentation and line ending as written
+        let start = line.len() - line.trim_start().len();
+        let end = line.trim_end().len();
+        upgraded.push_str(&line[..start]);
+        upgraded.push_str(&fields.join("|"));
+        upgraded.push_str(&line[end..]);
+    }
+
+    let reparsed = parse(&upgraded).context("The upgraded script doesn't parse")?;
+    if !same_behavior(&hltas, &reparsed) || frame_numbers(contents) != frame_numbers(&upgraded) {
+        bail!("Upgrading would change the behavior of the script");
+    }
+
+    Ok((upgraded, notes))
+}
+
+/// A formatted line of the frames section, frame bulks are kept apart to align their commands.
+enum FormattedLine {
+    Blank,
+    Line(String),
+    FrameBulk {
+        /// The frame bulk up to and including the frames field.
+        fields: String,
+        command: Option<String>,
+    },
+}
+
+/// Writes the frames section, aligning the commands of the frame bulks between blank lines.
+fn write_formatted_lines(formatted: &mut String, lines: &[FormattedLine]) {
+    for block in lines.split(|line| matches!(line, FormattedLine::Blank)) {
+        let width = block
+            .iter()
+            .filter_map(|line| match line {
+                FormattedLine::FrameBulk {
+                    fields,
+                    command: Some(_),
+                } => Some(fields.len()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default();
+
+        for line in block {
+            match line {
+                FormattedLine::Blank => {}
+                FormattedLine::Line(line) => formatted.push_str(line),
+                FormattedLine::FrameBulk {
+                    fields,
+                    command: None,
+                } => formatted.push_str(fields),
+                // the fields before the command can't be padded, the console ignores the spaces before a command
+                FormattedLine::FrameBulk {
+                    fields,
+                    command: Some(command),
+                } => formatted.push_str(&format!(
+                    "{fields}|{:1$}{command}",
+                    "",
+                    width - fields.len()
+                )),
+            }
+            formatted.push('\n');
+        }
+
+        formatted.push('\n');
+    }
+
+    // the split leaves a blank line after the last block
+    formatted.pop();
+}
+
+/// Rewrites a script in the canonical layout.
+///
+/// - Properties are written in a fixed order, unknown properties are kept after them.
+/// - Lines lose their indentation, numbers are written in their shortest decimal form and comments start with `// `.
+/// - Numbers keep their exact value as written, they aren't rounded to the `f32` the parser reads them as.
+/// - The commands of frame bulks between blank lines are aligned into one column.
+/// - Runs of blank lines between frames lines become a single one, other blank lines are removed.
+/// - Fails if the formatted script would behave differently, see [`same_behavior`], or if any number would change.
+pub fn format(contents: &str) -> Result<String> {
+    let hltas = parse(contents)?;
+    let mut lines = contents.lines();
+
+    // the version line
+    lines.find(|line| !line.trim().is_empty());
+
+    let mut unknown_properties = String::new();
+    for line in lines.by_ref() {
+        let line = line.trim();
+        if line == "frames" {
+            break;
+        }
+
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        if parts
+            .first()
+            .is_some_and(|name| !KNOWN_PROPERTIES.contains(name))
+        {
+            unknown_properties.push_str(&parts.join(" "));
+            unknown_properties.push('\n');
+        }
+    }
+
+    let mut properties = hltas.properties.clone();
+    properties.frametime_0ms = properties
+        .frametime_0ms
+        .map(|frametime| normalize_number(&frametime));
+
+    let header = header(&properties)?;
+    let frames_start = header.len() - "frames\n".len();
+
+    let mut formatted = header[..frames_start].to_string();
+    formatted.push_str(&unknown_properties);
+    formatted.push_str(&header[frames_start..]);
+
+    let mut formatted_lines = Vec::new();
+    let mut blank = false;
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            blank = !formatted_lines.is_empty();
+            continue;
+        }
+
+        let (_, parsed) =
+            hltas::read::line(line).map_err(|_| anyhow!("Failed to parse line '{line}'"))?;
+
+        if blank {
+            formatted_lines.push(FormattedLine::Blank);
+            blank = false;
+        }
+        formatted_lines.push(match parsed {
+            Line::FrameBulk(frame_bulk) => format_frame_bulk(line, &frame_bulk),
+            Line::Comment(comment) => FormattedLine::Line(format_comment(&comment)),
+            parsed => FormattedLine::Line(format_line(line, &parsed)),
+        });
+    }
+    write_formatted_lines(&mut formatted, &formatted_lines);
+
+    let reparsed = parse(&formatted).context("The formatted script doesn't parse")?;
+    if !same_behavior(&hltas, &reparsed) || frame_numbers(contents) != frame_numbers(&formatted) {
+        bail!("Formatting would change the behavior of the script");
+    }
+
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scripts from the test data of the hltas crate.
+    const SCRIPTS: &[(&str, &str)] = &[
+        ("bhop", include_str!("../tests/scripts/bhop.hltas")),
+        (
+            "bkz_goldbhop",
+            include_str!("../tests/scripts/bkz_goldbhop.hltas"),
+        ),
+        ("change", include_str!("../tests/scripts/change.hltas")),
+        (
+            "extra_letters",
+            include_str!("../tests/scripts/extra_letters.hltas"),
+        ),
+        (
+            "hazard_course",
+            include_str!("../tests/scripts/hazard_course.hltas"),
+        ),
+        (
+            "kz_synergy_x",
+            include_str!("../tests/scripts/kz_synergy_x.hltas"),
+        ),
+        (
+            "load_command",
+            include_str!("../tests/scripts/load_command.hltas"),
+        ),
+        (
+            "pitch_override",
+            include_str!("../tests/scripts/pitch_override.hltas"),
+        ),
+        (
+            "render_yaw_override",
+            include_str!("../tests/scripts/render_yaw_override.hltas"),
+        ),
+        ("rng", include_str!("../tests/scripts/rng.hltas")),
+        ("strafe", include_str!("../tests/scripts/strafe.hltas")),
+        (
+            "target_yaw_override",
+            include_str!("../tests/scripts/target_yaw_override.hltas"),
+        ),
+        (
+            "triggertest",
+            include_str!("../tests/scripts/triggertest.hltas"),
+        ),
+        (
+            "vectorial",
+            include_str!("../tests/scripts/vectorial.hltas"),
+        ),
+    ];
+
+    #[test]
+    fn format_round_trips_real_scripts() {
+        for (name, contents) in SCRIPTS {
+            let formatted = format(contents).unwrap_or_else(|err| panic!("{name}: {err:#}"));
+
+            let original = parse(contents).unwrap();
+            let reparsed = parse(&formatted).unwrap();
+            assert!(
+                same_behavior(&original, &reparsed),
+                "{name} behaves differently"
+            );
+            assert_eq!(
+                format(&formatted).unwrap(),
+                formatted,
+                "{name} formats again"
+            );
+        }
+    }
+
+    #[test]
+    fn format_normalizes_numbers() {
+        let formatted = format(
+            "version 1\nframetime0ms 0.00000010\nframes\n----------|------|------|0.0010|-|-|1\n----------|------|------|1e-3|090.500|-|1\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            formatted,
+            "version 1\nframetime0ms 0.0000001\nframes\n----------|------|------|0.001|-|-|1\n----------|------|------|0.001|90.5|-|1\n"
+        );
+    }
+
+    #[test]
+    fn format_keeps_numbers_past_f32_precision() {
+        let formatted = format(
+            "version 1\nframes\n----------|------|------|0.001|123.456789012345|-|1\ns53-------|------|------|0.001|100.000000001 0.10000000149 090.987654321 1.5|89.99999999|1\ntarget_yaw_override  123.456789012345 1.0\nchange yaw to 12.3456789012 over 0.50 s\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            formatted,
+            "version 1\nframes\n----------|------|------|0.001|123.456789012345|-|1\ns53-------|------|------|0.001|100.000000001 0.10000000149 90.987654321 1.5|89.99999999|1\ntarget_yaw_override 123.456789012345 1\nchange yaw to 12.3456789012 over 0.5 s\n"
+        );
+    }
+
+    #[test]
+    fn format_aligns_commands_between_blank_lines() {
+        let formatted = format(
+            "version 1\nframes\n----------|------|------|0.001|-|-|1|echo a\ns03-------|------|------|0.001|170|0|400\n----------|------|------|0.001|-|-|2951|  echo b  \n\n\n----------|------|------|0.001|-|-|1|echo c\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            formatted,
+            "version 1\nframes\n----------|------|------|0.001|-|-|1|   echo a\ns03-------|------|------|0.001|170|0|400\n----------|------|------|0.001|-|-|2951|echo b\n\n----------|------|------|0.001|-|-|1|echo c\n"
+        );
+    }
+
+    #[test]
+    fn upgrade_rewrites_older_forms() {
+        let (upgraded, notes) = upgrade(
+            "version 1\nunknown_property 1\nframes\n----------skjdhfjkshg|------|------|0.001|-|-|0\n----------|------|------|0.001|-|-|-\n----------|------|------|0.001|-|-|5\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            upgraded,
+            "version 1\nunknown_property 1\nframes\n----------|------|------|0.001|-|-|1\n----------|------|------|0.001|-|-|1\n----------|------|------|0.001|-|-|5\n"
+        );
+        assert_eq!(
+            notes.iter().map(|note| note.line).collect::<Vec<_>>(),
+            [4, 4, 5]
+        );
+    }
+
+    #[test]
+    fn upgrade_keeps_the_layout() {
+        let contents = "version 1\nframes\n  ----------|------|------|0.0010|123.456789012345|-|5|  echo a\r\n\n\n----------|------|------|0.001|-|-|-\n";
+        let (upgraded, notes) = upgrade(contents).unwrap();
+
+        assert_eq!(
+            upgraded,
+            "version 1\nframes\n  ----------|------|------|0.0010|123.456789012345|-|5|  echo a\r\n\n\n----------|------|------|0.001|-|-|1\n"
+        );
+        assert_eq!(notes.len(), 1);
+
+        let (upgraded_again, notes) = upgrade(&upgraded).unwrap();
+        assert_eq!(upgraded_again, upgraded);
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn normalize_number_writes_shortest_decimal() {
+        for (number, normalized) in [
+            ("0.0010", "0.001"),
+            ("1e-3", "0.001"),
+            ("1.5E2", "150"),
+            ("+.5", "0.5"),
+            ("00100.0", "100"),
+            ("-0.000", "0"),
+            ("-2.50", "-2.5"),
+            ("abc", "abc"),
+        ] {
+            assert_eq!(normalize_number(number), normalized, "{number}");
+        }
+    }
+
+    #[test]
+    fn same_behavior_compares_numbers_by_value() {
+        let script = |frame_time: &str| {
+            parse(&format!(
+                "version 1\nframes\n----------|------|------|{frame_time}|-|-|1\n"
+            ))
+            .unwrap()
+        };
+
+        assert!(same_behavior(&script("0.001"), &script("0.0010")));
+        assert!(!same_behavior(&script("0.001"), &script("0.002")));
+    }
+}
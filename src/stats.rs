@@ -0,0 +1,121 @@
+//! Statistics of the frame bulks of scripts.
+
+use std::collections::BTreeMap;
+
+use hltas::HLTAS;
+use serde::Serialize;
+
+use crate::script;
+
+/// Name of the strafe frames of frame bulks that don't strafe.
+pub const NO_STRAFE: &str = "none";
+
+/// Names of the actions of frame bulks holding the jump and duck keys, next to the automatic actions.
+pub const JUMP_KEY: &str = "jump key";
+pub const DUCK_KEY: &str = "duck key";
+
+#[derive(Serialize, Default, Debug, Clone)]
+pub struct Stats {
+    pub frame_bulks: u64,
+    pub frames: u64,
+    /// Frames spent in each strafe type.
+    pub strafe_frames: BTreeMap<String, u64>,
+    /// Frame bulks using each automatic action or holding the jump or duck key.
+    ///
+    /// - Jumps themselves can't be counted without running the script, they depend on when the player is on the ground.
+    pub action_bulks: BTreeMap<String, u64>,
+    /// Frames each action of [`Stats::action_bulks`] is used in.
+    pub action_frames: BTreeMap<String, u64>,
+    /// Frames run at each frame time.
+    pub frametime_frames: BTreeMap<String, u64>,
+    /// Frame bulks that run a console command.
+    pub console_command_bulks: u64,
+}
+
+impl Stats {
+    pub fn from_hltas(hltas: &HLTAS) -> Self {
+        let mut stats = Stats::default();
+
+        for frame_bulk in hltas.frame_bulks() {
+            let frames = u64::from(frame_bulk.frame_count.get());
+            stats.frame_bulks += 1;
+            stats.frames += frames;
+
+            let strafe_type = script::strafe_type(frame_bulk).unwrap_or(NO_STRAFE);
+            *stats
+                .strafe_frames
+                .entry(strafe_type.to_string())
+                .or_default() += frames;
+
+            let action_keys = &frame_bulk.action_keys;
+            let held_keys = [(action_keys.jump, JUMP_KEY), (action_keys.duck, DUCK_KEY)]
+                .into_iter()
+                .filter(|(held, _)| *held)
+                .map(|(_, name)| name);
+            for action in script::auto_actions(frame_bulk)
+                .into_iter()
+                .chain(held_keys)
+            {
+                *stats.action_bulks.entry(action.to_string()).or_default() += 1;
+                *stats.action_frames.entry(action.to_string()).or_default() += frames;
+            }
+
+            // the number as the game reads it, so `0.0010` and `0.001` count together
+            let frame_time = script::frame_time(frame_bulk).to_string();
+            *stats.frametime_frames.entry(frame_time).or_default() += frames;
+
+            if !script::console_commands(frame_bulk).is_empty() {
+                stats.console_command_bulks += 1;
+            }
+        }
+
+        stats
+    }
+
+    /// Adds the stats of another script, for the totals of a project.
+    pub fn add(&mut self, other: &Stats) {
+        self.frame_bulks += other.frame_bulks;
+        self.frames += other.frames;
+        self.console_command_bulks += other.console_command_bulks;
+
+        for (total, counts) in [
+            (&mut self.strafe_frames, &other.strafe_frames),
+            (&mut self.action_bulks, &other.action_bulks),
+            (&mut self.action_frames, &other.action_frames),
+            (&mut self.frametime_frames, &other.frametime_frames),
+        ] {
+            for (name, count) in counts {
+                *total.entry(name.clone()).or_default() += count;
+            }
+        }
+    }
+
+    /// The stats as `(stat, name, value)` rows, `name` is empty for stats without one.
+    pub fn rows(&self) -> Vec<(&'static str, String, u64)> {
+        let mut rows = vec![
+            ("frame_bulks", String::new(), self.frame_bulks),
+            ("frames", String::new(), self.frames),
+        ];
+
+        for (stat, counts) in [
+            ("strafe_frames", &self.strafe_frames),
+            ("action_bulks", &self.action_bulks),
+            ("action_frames", &self.action_frames),
+            ("frametime_frames", &self.frametime_frames),
+        ] {
+            rows.extend(
+                counts
+                    .iter()
+                    .map(|(name, count)| (stat, name.clone(), *count)),
+            );
+        }
+
+        rows.push((
+            "console_command_bulks",
+            String::new(),
+            self.console_command_bulks,
+        ));
+
+        rows
+    }
+}